@@ -53,18 +53,10 @@ fn main_loop(io: &mut IO) {
                 l_b.unique.set_text(tx);
                 ui.recompute_layout();
 
-                return Signal::Modal(Box::new(|io: &mut IO| {
-                    io.menu(|out, menu| {
-                        let i = menu.on_mouse(|_| Signal::Break);
-                        out.brush().region(rect(2, 2, 80, 80)).interactor(i, (255, 255)).putfs("HELLO, ROBOT!");
-
-                        menu.on_key(OnKey::only(Keycode::A).pressed(), |k| {
-                            println!("key A: {:?}", k);
-                            Signal::Continue
-                        })
-                    });
+                return MessageBox::ask("Robot", "HELLO, ROBOT!", MessageButtons::OkCancel).run(&ui, |_, result| {
+                    println!("robot says: {:?}", result);
                     Signal::Refresh
-                }));
+                });
             } 
         }));
     });
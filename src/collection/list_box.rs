@@ -0,0 +1,197 @@
+use std::{cell::Cell, rc::Rc};
+
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetCommon, WidgetMenu, Widgetlike, layout::{Scrolls, draw_scrollbar, fix_offset}, widget::LayoutHacks};
+
+use super::{ListModel, SelectionMode, row_selection::{RowSelection, RowWidget, bind_row_keys, selection_changed}};
+
+pub type ListBox = Widget<ListBoxState>;
+
+pub struct ListBoxState {
+    model: Option<Rc<dyn ListModel>>,
+    rows: RowSelection,
+
+    offset: Cell<f64>,
+    scroll_target: Cell<Option<usize>>,
+    viewport_rows: Cell<usize>,
+
+    pub selection_mode: SelectionMode,
+    pub visible_rows: usize,
+    pub on_select: Option<Box<dyn FnMut(UI, &mut WidgetCommon<ListBoxState>) -> Signal>>,
+
+    pub layout_hacks: LayoutHacks,
+}
+
+impl Scrolls for ListBoxState {
+    fn scroll_offset(&self) -> &Cell<f64> { &self.offset }
+}
+
+impl ListBoxState {
+    pub fn set_model(&mut self, model: Rc<dyn ListModel>) {
+        self.model = Some(model);
+        self.rows = RowSelection::new();
+        self.offset.replace(0.0);
+    }
+
+    pub fn model(&self) -> Option<Rc<dyn ListModel>> {
+        self.model.clone()
+    }
+
+    pub fn selected(&self) -> Vec<usize> {
+        self.rows.selected().collect()
+    }
+
+    pub fn set_selected(&mut self, rows: impl IntoIterator<Item=usize>) {
+        self.rows.set(rows);
+        self.scroll_target.replace(Some(self.rows.cursor));
+    }
+
+    pub fn scroll_to(&self, row: usize) {
+        self.scroll_target.replace(Some(row));
+    }
+
+    pub fn set_on_select(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<ListBoxState>) -> Signal) {
+        self.on_select = Some(Box::new(cmd))
+    }
+}
+
+impl Widgetlike for ListBoxState {
+    fn create() -> Self {
+        ListBoxState {
+            model: None,
+            rows: RowSelection::new(),
+
+            offset: Cell::new(0.0),
+            scroll_target: Cell::new(None),
+            viewport_rows: Cell::new(1),
+
+            selection_mode: SelectionMode::Single,
+            visible_rows: 8,
+            on_select: None,
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        menu.register_focus();
+
+        let theme = menu.ui.theme().list;
+        brush.fill(FSem::new().color(theme.color));
+        brush.bevel_w95(theme.bevel);
+
+        let model = if let Some(m) = &self.model { m.clone() } else { return };
+        let row_count = model.row_count();
+        let row_height = model.row_height().max(1) as isize;
+
+        if selected {
+            bind_row_keys(&menu);
+        }
+
+        let height = brush.rect().height();
+        let inner_height = row_count as isize * row_height;
+        let has_scrollbar = inner_height > height;
+        let width = brush.rect().width() - if has_scrollbar { 2 } else { 0 };
+        self.viewport_rows.replace((height / row_height).max(1) as usize);
+
+        if let Some(target) = self.scroll_target.take() {
+            let top = target as isize * row_height;
+            let bottom = top + row_height;
+            let offset = self.offset.get() as isize;
+            if top < offset {
+                self.offset.replace(top as f64);
+            } else if bottom > offset + height {
+                self.offset.replace((bottom - height) as f64);
+            }
+        }
+        let offset_to_use = fix_offset(&self.offset, inner_height, height);
+
+        if has_scrollbar {
+            draw_scrollbar(brush.clone(), &menu, inner_height, offset_to_use);
+        }
+
+        // only the visible rows exist as far as layout and drawing are concerned
+        let first = (offset_to_use / row_height) as usize;
+        let last = (((offset_to_use + height + row_height - 1) / row_height) as usize).min(row_count);
+        for row in first..last {
+            let interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        ui.select(this);
+                        let mode = this.unique.selection_mode;
+                        let (shift, control) = ui.modifiers();
+                        this.unique.rows.click(row, shift, control, mode);
+                        return selection_changed(ui, this)
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag { mouse_button: MouseButton::Left, start_point, now_point, .. } => {
+                        let from = this.unique.row_at(start_point.y, row_height);
+                        let to = this.unique.row_at(now_point.y, row_height);
+                        let mode = this.unique.selection_mode;
+                        this.unique.rows.drag(from, to, mode);
+                        return selection_changed(ui, this)
+                    }
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+
+            let row_brush = brush.region(rect(0, row as isize * row_height - offset_to_use, width, row_height));
+            let color = if self.rows.is_selected(row) {
+                theme.selected
+            } else if selected && row == self.rows.cursor {
+                theme.cursor
+            } else {
+                theme.color
+            };
+            row_brush.interactor(interactor, theme.preclick).fill(FSem::new().color(color));
+            model.render_row(row, row_brush);
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        let row_height = self.model.as_ref().map(|m| m.row_height().max(1)).unwrap_or(2) as isize;
+        InternalWidgetDimensions { 
+            min: size2(4, row_height.max(4)),
+            preferred: size2(20, self.visible_rows as isize * row_height),
+            max: None,
+            align_size_to: size2(1, 1),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl RowWidget for ListBoxState {
+    fn row_selection(&mut self) -> &mut RowSelection { &mut self.rows }
+
+    fn row_count(&self) -> usize {
+        self.model.as_ref().map(|m| m.row_count()).unwrap_or(0)
+    }
+
+    fn page_rows(&self) -> usize { self.viewport_rows.get() }
+
+    fn selection_mode(&self) -> SelectionMode { self.selection_mode }
+
+    fn scroll_to_row(&self, row: usize) { self.scroll_to(row) }
+
+    fn on_select(&mut self) -> &mut Option<Box<dyn FnMut(UI, &mut WidgetCommon<Self>) -> Signal>> { &mut self.on_select }
+}
+
+impl ListBoxState {
+    fn row_at(&self, y: isize, row_height: isize) -> usize {
+        let mut offset = self.offset.get() as isize;
+        offset -= offset % 2;
+        let row = ((y + offset) / row_height).max(0) as usize;
+        row.min(self.row_count().saturating_sub(1))
+    }
+}
@@ -0,0 +1,11 @@
+mod list_box;
+mod model;
+mod row_selection;
+mod table;
+mod tree_view;
+
+pub use list_box::{ListBox, ListBoxState};
+pub use model::{ListModel, TableModel, TreeModel};
+pub use row_selection::SelectionMode;
+pub use table::{ColumnWidth, Table, TableState};
+pub use tree_view::{TreeView, TreeViewState};
@@ -0,0 +1,52 @@
+use std::cmp::Ordering;
+
+use chiropterm::Brush;
+
+// Backs a ListBox. Only rows that are on the screen get rendered, so the list can be as long as you like
+pub trait ListModel: 'static {
+    fn row_count(&self) -> usize;
+    fn render_row(&self, row: usize, brush: Brush);
+
+    // Every row is the same height, so the list never has to measure them
+    fn row_height(&self) -> usize { 2 }
+}
+
+impl ListModel for Vec<String> {
+    fn row_count(&self) -> usize { self.len() }
+
+    fn render_row(&self, row: usize, brush: Brush) {
+        brush.putfs(&self[row]);
+    }
+}
+
+// Backs a Table. Like ListModel, only the rows on the screen are ever asked for
+pub trait TableModel: 'static {
+    fn row_count(&self) -> usize;
+    fn column_count(&self) -> usize;
+    fn header(&self, column: usize) -> String;
+    fn cell_text(&self, row: usize, column: usize) -> String;
+
+    fn render_cell(&self, row: usize, column: usize, brush: Brush) {
+        brush.putfs(&self.cell_text(row, column));
+    }
+
+    // Used when the user clicks a header to sort by that column
+    fn compare(&self, column: usize, a: usize, b: usize) -> Ordering {
+        self.cell_text(a, column).cmp(&self.cell_text(b, column))
+    }
+
+    fn row_height(&self) -> usize { 2 }
+}
+
+// Backs a TreeView. Nodes are identified by whatever ids the model likes.
+// Children are only asked for when their parent is first expanded
+pub trait TreeModel: 'static {
+    fn roots(&self) -> Vec<u64>;
+    fn has_children(&self, node: u64) -> bool;
+    fn children(&self, node: u64) -> Vec<u64>;
+    fn label(&self, node: u64) -> String;
+
+    fn render_node(&self, node: u64, brush: Brush) {
+        brush.putfs(&self.label(node));
+    }
+}
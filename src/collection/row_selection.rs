@@ -0,0 +1,232 @@
+use std::collections::BTreeSet;
+
+use chiropterm::{Keycode, OnKey, Signal};
+
+use crate::{UI, WidgetCommon, WidgetMenu, Widgetlike};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    Single,
+    Multiple,
+}
+
+// Which rows of a list or table are selected, and where the keyboard cursor is
+pub(crate) struct RowSelection {
+    selected: BTreeSet<usize>,
+    pub(crate) cursor: usize,
+    anchor: usize,  // the fixed end of a shift-selection
+}
+
+impl RowSelection {
+    pub(crate) fn new() -> RowSelection {
+        RowSelection { selected: BTreeSet::new(), cursor: 0, anchor: 0 }
+    }
+
+    pub(crate) fn is_selected(&self, row: usize) -> bool {
+        self.selected.contains(&row)
+    }
+
+    pub(crate) fn selected(&self) -> impl '_+Iterator<Item=usize> {
+        self.selected.iter().cloned()
+    }
+
+    pub(crate) fn set(&mut self, rows: impl IntoIterator<Item=usize>) {
+        self.selected = rows.into_iter().collect();
+        if let Some(first) = self.selected.iter().next() {
+            self.cursor = *first;
+            self.anchor = *first;
+        }
+    }
+
+    // Rows can disappear out from under us when the model changes
+    pub(crate) fn clamp(&mut self, row_count: usize) {
+        self.selected = self.selected.range(..row_count).cloned().collect();
+        let last = row_count.saturating_sub(1);
+        self.cursor = self.cursor.min(last);
+        self.anchor = self.anchor.min(last);
+    }
+
+    // Moves the cursor, selecting just the new row -- or, when extending, everything back to the anchor
+    pub(crate) fn move_cursor(&mut self, row: usize, extend: bool, mode: SelectionMode) {
+        self.cursor = row;
+        if extend && mode == SelectionMode::Multiple {
+            let (l, r) = (self.anchor.min(row), self.anchor.max(row));
+            self.selected = (l..=r).collect();
+        } else {
+            self.anchor = row;
+            self.selected.clear();
+            self.selected.insert(row);
+        }
+    }
+
+    pub(crate) fn toggle_cursor(&mut self, mode: SelectionMode) {
+        let row = self.cursor;
+        if mode == SelectionMode::Single {
+            return self.move_cursor(row, false, mode)
+        }
+        self.anchor = row;
+        if !self.selected.remove(&row) { self.selected.insert(row); }
+    }
+
+    // Shift-click selects back to the anchor, control-click adds or removes just that row
+    pub(crate) fn click(&mut self, row: usize, shift: bool, control: bool, mode: SelectionMode) {
+        if control && !shift {
+            self.cursor = row;
+            return self.toggle_cursor(mode)
+        }
+        self.move_cursor(row, shift, mode)
+    }
+
+    pub(crate) fn drag(&mut self, from: usize, to: usize, mode: SelectionMode) {
+        self.anchor = from;
+        self.move_cursor(to, true, mode);
+    }
+}
+
+// Lists, tables and trees all scroll through rows with the same keys
+pub(crate) trait RowWidget: Widgetlike {
+    fn row_selection(&mut self) -> &mut RowSelection;
+    fn row_count(&self) -> usize;
+    fn page_rows(&self) -> usize;
+    fn selection_mode(&self) -> SelectionMode;
+    fn scroll_to_row(&self, row: usize);
+    fn on_select(&mut self) -> &mut Option<Box<dyn FnMut(UI, &mut WidgetCommon<Self>) -> Signal>>;
+}
+
+pub(crate) fn bind_row_keys<'frame, T: RowWidget>(menu: &WidgetMenu<'frame, T>) {
+    bind_motion(menu, Keycode::Up, |_| -1);
+    bind_motion(menu, Keycode::Down, |_| 1);
+    bind_motion(menu, Keycode::PageUp, |this| -(this.page_rows() as isize));
+    bind_motion(menu, Keycode::PageDown, |this| this.page_rows() as isize);
+    bind_motion(menu, Keycode::Home, |_| isize::MIN / 2);
+    bind_motion(menu, Keycode::End, |_| isize::MAX / 2);
+
+    menu.on_key_hprio(OnKey::only(Keycode::Space).pressed(), |ui, this, _| {
+        let mode = this.unique.selection_mode();
+        this.unique.row_selection().toggle_cursor(mode);
+        selection_changed(ui, this)
+    });
+}
+
+// Binds a key to a cursor motion, and shift+key to the same motion extending the selection
+fn bind_motion<'frame, T: RowWidget>(menu: &WidgetMenu<'frame, T>, key: Keycode, amount: fn(&T) -> isize) {
+    for extend in [false, true] {
+        let k = if extend { OnKey::only(key).shift().pressed() } else { OnKey::only(key).pressed() };
+        menu.on_key_hprio(k, move |ui, this, _| {
+            let amt = amount(&this.unique);
+            move_cursor(ui, this, amt, extend)
+        });
+    }
+}
+
+pub(crate) fn move_cursor<T: RowWidget>(ui: UI, this: &mut WidgetCommon<T>, amount: isize, extend: bool) -> Signal {
+    let row_count = this.unique.row_count();
+    if row_count == 0 { return Signal::Continue }
+
+    let cursor = this.unique.row_selection().cursor;
+    let row = (cursor as isize).saturating_add(amount).max(0).min(row_count as isize - 1) as usize;
+    let mode = this.unique.selection_mode();
+    this.unique.row_selection().move_cursor(row, extend, mode);
+    this.unique.scroll_to_row(row);
+    selection_changed(ui, this)
+}
+
+pub(crate) fn selection_changed<T: RowWidget>(ui: UI, this: &mut WidgetCommon<T>) -> Signal {
+    let row_count = this.unique.row_count();
+    this.unique.row_selection().clamp(row_count);
+
+    let on_select = this.unique.on_select().take();
+    if let Some(mut c) = on_select {
+        let result = c(ui, this);
+        this.unique.on_select().replace(c);
+        return result
+    }
+    Signal::Refresh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected(rows: &RowSelection) -> Vec<usize> {
+        rows.selected().collect()
+    }
+
+    #[test]
+    fn moving_selects_just_the_new_row() {
+        let mut rows = RowSelection::new();
+        rows.move_cursor(3, false, SelectionMode::Multiple);
+        rows.move_cursor(5, false, SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![5]);
+        assert_eq!(rows.cursor, 5);
+    }
+
+    #[test]
+    fn extending_selects_back_to_the_anchor() {
+        let mut rows = RowSelection::new();
+        rows.move_cursor(3, false, SelectionMode::Multiple);
+        rows.move_cursor(5, true, SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![3, 4, 5]);
+        rows.move_cursor(1, true, SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn single_mode_never_extends() {
+        let mut rows = RowSelection::new();
+        rows.move_cursor(3, false, SelectionMode::Single);
+        rows.move_cursor(5, true, SelectionMode::Single);
+        assert_eq!(selected(&rows), vec![5]);
+        rows.toggle_cursor(SelectionMode::Single);
+        assert_eq!(selected(&rows), vec![5]);
+    }
+
+    #[test]
+    fn toggling_adds_and_removes_the_cursor_row() {
+        let mut rows = RowSelection::new();
+        rows.move_cursor(1, false, SelectionMode::Multiple);
+        rows.cursor = 4;
+        rows.toggle_cursor(SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![1, 4]);
+        rows.toggle_cursor(SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![1]);
+    }
+
+    #[test]
+    fn clicks_with_modifiers_extend_or_toggle() {
+        let mut rows = RowSelection::new();
+        rows.click(2, false, false, SelectionMode::Multiple);
+        rows.click(4, true, false, SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![2, 3, 4]);
+        rows.click(7, false, true, SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![2, 3, 4, 7]);
+        rows.click(3, false, true, SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![2, 4, 7]);
+        // the toggled row is the new anchor
+        rows.click(5, true, false, SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![3, 4, 5]);
+        rows.click(1, false, true, SelectionMode::Single);
+        assert_eq!(selected(&rows), vec![1]);
+    }
+
+    #[test]
+    fn dragging_selects_the_span() {
+        let mut rows = RowSelection::new();
+        rows.drag(6, 4, SelectionMode::Multiple);
+        assert_eq!(selected(&rows), vec![4, 5, 6]);
+        assert_eq!(rows.cursor, 4);
+    }
+
+    #[test]
+    fn clamping_drops_rows_that_are_gone() {
+        let mut rows = RowSelection::new();
+        rows.set(vec![2, 7, 9]);
+        rows.cursor = 9;
+        rows.clamp(8);
+        assert_eq!(selected(&rows), vec![2, 7]);
+        assert_eq!(rows.cursor, 7);
+        rows.clamp(0);
+        assert_eq!(selected(&rows), Vec::<usize>::new());
+        assert_eq!(rows.cursor, 0);
+    }
+}
@@ -0,0 +1,372 @@
+use std::{cell::{Cell, RefCell}, rc::Rc};
+
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetCommon, WidgetMenu, Widgetlike, input::text_width, layout::{Fallback, Flex, FlexItem, Scrolls, distribute, draw_scrollbar, fix_offset}, widget::LayoutHacks};
+
+use super::{SelectionMode, TableModel, row_selection::{RowSelection, RowWidget, bind_row_keys, selection_changed}};
+
+pub type Table = Widget<TableState>;
+
+const HEADER_HEIGHT: isize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnWidth {
+    Fixed(usize),
+    // shares out whatever space is left, like a spacer with this count
+    Fraction(usize),
+    // as wide as the header or the widest cell in the column
+    FitContent,
+}
+
+pub struct TableState {
+    model: Option<Rc<dyn TableModel>>,
+    column_widths: Vec<ColumnWidth>,  // columns past the end are FitContent
+    order: RefCell<Vec<usize>>,  // view row -> model row
+    sort: Option<(usize, bool)>,  // column, ascending
+    rows: RowSelection,  // in view rows
+
+    offset: Cell<f64>,
+    scroll_target: Cell<Option<usize>>,
+    viewport_rows: Cell<usize>,
+    last_widths: RefCell<Vec<isize>>,
+    content_widths: RefCell<Option<Vec<isize>>>,  // widest cell in each column, over every row. None if the rows changed
+
+    pub selection_mode: SelectionMode,
+    pub visible_rows: usize,
+    pub on_select: Option<Box<dyn FnMut(UI, &mut WidgetCommon<TableState>) -> Signal>>,
+
+    pub layout_hacks: LayoutHacks,
+}
+
+impl Scrolls for TableState {
+    fn scroll_offset(&self) -> &Cell<f64> { &self.offset }
+}
+
+impl TableState {
+    pub fn set_model(&mut self, model: Rc<dyn TableModel>) {
+        self.model = Some(model);
+        self.rows = RowSelection::new();
+        self.offset.replace(0.0);
+        self.resort();
+    }
+
+    pub fn model(&self) -> Option<Rc<dyn TableModel>> {
+        self.model.clone()
+    }
+
+    pub fn set_column_width(&mut self, column: usize, width: ColumnWidth) {
+        if self.column_widths.len() <= column {
+            self.column_widths.resize(column + 1, ColumnWidth::FitContent);
+        }
+        self.column_widths[column] = width;
+    }
+
+    pub fn column_width(&self, column: usize) -> ColumnWidth {
+        self.column_widths.get(column).cloned().unwrap_or(ColumnWidth::FitContent)
+    }
+
+    pub fn sort_by(&mut self, column: usize, ascending: bool) {
+        self.sort = Some((column, ascending));
+        self.reorder();
+    }
+
+    pub fn sorted_by(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+
+    // Call this when the model's rows change, to re-apply the sort
+    pub fn resort(&mut self) {
+        self.content_widths.replace(None);
+        self.reorder();
+    }
+
+    fn reorder(&mut self) {
+        let selected = self.selected();
+        let cursor = self.order.borrow().get(self.rows.cursor).cloned();
+
+        self.rebuild_order();
+
+        // selection follows the model rows, not their old positions
+        let order = self.order.borrow();
+        let view_row = |model_row: usize| order.iter().position(|r| *r == model_row);
+        self.rows.set(selected.into_iter().filter_map(view_row));
+        if let Some(c) = cursor.and_then(view_row) { self.rows.cursor = c; }
+    }
+
+    // Selected rows, as model rows
+    pub fn selected(&self) -> Vec<usize> {
+        let order = self.order.borrow();
+        self.rows.selected().filter_map(|r| order.get(r).cloned()).collect()
+    }
+
+    pub fn scroll_to(&self, view_row: usize) {
+        self.scroll_target.replace(Some(view_row));
+    }
+
+    pub fn set_on_select(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<TableState>) -> Signal) {
+        self.on_select = Some(Box::new(cmd))
+    }
+}
+
+impl Widgetlike for TableState {
+    fn create() -> Self {
+        TableState {
+            model: None,
+            column_widths: vec![],
+            order: RefCell::new(vec![]),
+            sort: None,
+            rows: RowSelection::new(),
+
+            offset: Cell::new(0.0),
+            scroll_target: Cell::new(None),
+            viewport_rows: Cell::new(1),
+            last_widths: RefCell::new(vec![]),
+            content_widths: RefCell::new(None),
+
+            selection_mode: SelectionMode::Single,
+            visible_rows: 8,
+            on_select: None,
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        menu.register_focus();
+
+        let theme = menu.ui.theme();
+        brush.fill(FSem::new().color(theme.list.color));
+        brush.bevel_w95(theme.list.bevel);
+
+        let model = if let Some(m) = &self.model { m.clone() } else { return };
+        if self.order.borrow().len() != model.row_count() {
+            self.content_widths.replace(None);
+            self.rebuild_order();
+        }
+        let row_count = model.row_count();
+        let row_height = model.row_height().max(1) as isize;
+
+        if selected {
+            bind_row_keys(&menu);
+        }
+
+        let body_height = brush.rect().height() - HEADER_HEIGHT;
+        let inner_height = row_count as isize * row_height;
+        let has_scrollbar = inner_height > body_height;
+        let width = brush.rect().width() - if has_scrollbar { 2 } else { 0 };
+        self.viewport_rows.replace((body_height / row_height).max(1) as usize);
+
+        if let Some(target) = self.scroll_target.take() {
+            let top = target as isize * row_height;
+            let bottom = top + row_height;
+            let offset = self.offset.get() as isize;
+            if top < offset {
+                self.offset.replace(top as f64);
+            } else if bottom > offset + body_height {
+                self.offset.replace((bottom - body_height) as f64);
+            }
+        }
+        let offset_to_use = fix_offset(&self.offset, inner_height, body_height);
+
+        let first = (offset_to_use / row_height) as usize;
+        let last = (((offset_to_use + body_height + row_height - 1) / row_height) as usize).min(row_count);
+
+        let order = self.order.borrow();
+        let widths = distribute(&self.column_items(&*model), width, Fallback::Nobody).to_vec();
+        self.last_widths.replace(widths.clone());
+
+        // header: click to sort, drag the rightmost cell of a header to resize its column
+        let mut x = 0;
+        for (column, w) in widths.iter().cloned().enumerate() {
+            let sort_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        ui.select(this);
+                        let ascending = this.unique.sort != Some((column, true));
+                        this.unique.sort_by(column, ascending);
+                        return Signal::Refresh
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+            let resize_interactor = menu.on_mouse(move |_, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Drag { mouse_button: MouseButton::Left, last_point, now_point, .. } => {
+                        let current = this.unique.last_widths.borrow().get(column).cloned().unwrap_or(1) - 1;
+                        let new_width = (current + now_point.x - last_point.x).max(1);
+                        this.unique.set_column_width(column, ColumnWidth::Fixed(new_width as usize));
+                        return Signal::Refresh
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+
+            let header = brush.region(rect(x, 0, w, HEADER_HEIGHT));
+            header.fill(FSem::new().color(theme.window.color));
+            header.bevel_w95(theme.button.bevel);
+            let label = header.region(rect(0, 0, (w - 1).max(0), HEADER_HEIGHT)).interactor(sort_interactor, theme.button.preclick);
+            label.putfs(&model.header(column));
+            match self.sort {
+                // too narrow a column just doesn't get an arrow
+                Some((c, ascending)) if c == column && w >= 4 => {
+                    // CP437 up and down triangles
+                    label.region(rect(w - 3, 0, 1, HEADER_HEIGHT)).putch(if ascending { 0x1eu16 } else { 0x1fu16 });
+                }
+                _ => {}
+            }
+            header.region(rect(w - 1, 0, 1, HEADER_HEIGHT)).interactor(resize_interactor, theme.button.preclick).putch(0xb3u16);
+            x += w;
+        }
+
+        let body = brush.region(rect(0, HEADER_HEIGHT, brush.rect().width(), body_height));
+        if has_scrollbar {
+            draw_scrollbar(body.clone(), &menu, inner_height, offset_to_use);
+        }
+
+        for row in first..last {
+            let interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        ui.select(this);
+                        let mode = this.unique.selection_mode;
+                        let (shift, control) = ui.modifiers();
+                        this.unique.rows.click(row, shift, control, mode);
+                        return selection_changed(ui, this)
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag { mouse_button: MouseButton::Left, start_point, now_point, .. } => {
+                        let from = this.unique.row_at(start_point.y - HEADER_HEIGHT, row_height);
+                        let to = this.unique.row_at(now_point.y - HEADER_HEIGHT, row_height);
+                        let mode = this.unique.selection_mode;
+                        this.unique.rows.drag(from, to, mode);
+                        return selection_changed(ui, this)
+                    }
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+
+            let row_brush = body.region(rect(0, row as isize * row_height - offset_to_use, width, row_height));
+            let color = if self.rows.is_selected(row) {
+                theme.list.selected
+            } else if selected && row == self.rows.cursor {
+                theme.list.cursor
+            } else {
+                theme.list.color
+            };
+            row_brush.interactor(interactor, theme.list.preclick).fill(FSem::new().color(color));
+
+            let mut x = 0;
+            for (column, w) in widths.iter().cloned().enumerate() {
+                model.render_cell(order[row], column, row_brush.region(rect(x, 0, (w - 1).max(0), row_height)));
+                x += w;
+            }
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        let model = if let Some(m) = &self.model { m.clone() } else { return InternalWidgetDimensions::zero() };
+        let row_height = model.row_height().max(1) as isize;
+
+        let columns = self.column_items(&*model);
+        InternalWidgetDimensions {
+            min: size2(columns.iter().map(|c| c.min).sum(), HEADER_HEIGHT + row_height),
+            preferred: size2(
+                columns.iter().map(|c| c.preferred).sum(), 
+                HEADER_HEIGHT + self.visible_rows as isize * row_height,
+            ),
+            max: None,
+            align_size_to: size2(1, 1),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl RowWidget for TableState {
+    fn row_selection(&mut self) -> &mut RowSelection { &mut self.rows }
+
+    fn row_count(&self) -> usize {
+        self.model.as_ref().map(|m| m.row_count()).unwrap_or(0)
+    }
+
+    fn page_rows(&self) -> usize { self.viewport_rows.get() }
+
+    fn selection_mode(&self) -> SelectionMode { self.selection_mode }
+
+    fn scroll_to_row(&self, row: usize) { self.scroll_to(row) }
+
+    fn on_select(&mut self) -> &mut Option<Box<dyn FnMut(UI, &mut WidgetCommon<Self>) -> Signal>> { &mut self.on_select }
+}
+
+impl TableState {
+    fn rebuild_order(&self) {
+        let model = if let Some(m) = &self.model { m.clone() } else { return };
+        let mut order: Vec<usize> = (0..model.row_count()).collect();
+        if let Some((column, ascending)) = self.sort {
+            order.sort_by(|a, b| {
+                let o = model.compare(column, *a, *b);
+                if ascending { o } else { o.reverse() }
+            });
+        }
+        self.order.replace(order);
+    }
+
+    fn row_at(&self, y: isize, row_height: isize) -> usize {
+        let mut offset = self.offset.get() as isize;
+        offset -= offset % 2;
+        let row = ((y + offset) / row_height).max(0) as usize;
+        row.min(self.row_count().saturating_sub(1))
+    }
+
+    // Measured over every row, not just the ones on screen, so columns don't change width as the table scrolls.
+    // That's a pass over the whole model, so it's kept until the rows change (see resort) -- sorting doesn't redo it
+    fn content_width(&self, model: &dyn TableModel, column: usize) -> isize {
+        if self.content_widths.borrow().is_none() {
+            let widths = (0..model.column_count()).map(|c| {
+                (0..model.row_count())
+                    .map(|r| text_width(&model.cell_text(r, c)) as isize + 1)
+                    .max().unwrap_or(0)
+            }).collect();
+            self.content_widths.replace(Some(widths));
+        }
+        self.content_widths.borrow().as_ref().and_then(|w| w.get(column).cloned()).unwrap_or(0)
+    }
+
+    // Each column, sized by the header and the cells, for flex::distribute.
+    // Spare room only goes to Fraction columns: if there aren't any, it's left empty on the right
+    fn column_items(&self, model: &dyn TableModel) -> Vec<FlexItem> {
+        (0..model.column_count()).map(|column| {
+            // one cell for the separator, two more for the sort arrow
+            let header_w = text_width(&model.header(column)) as isize + 3;
+            let (min, preferred, spacer_count) = match self.column_width(column) {
+                ColumnWidth::Fixed(w) => (w as isize + 1, w as isize + 1, 0),
+                ColumnWidth::Fraction(n) => (4, header_w, n),
+                ColumnWidth::FitContent => {
+                    let preferred = header_w.max(self.content_width(model, column));
+                    (4.min(preferred), preferred, 0)
+                }
+            };
+            FlexItem { min, preferred, align: 1, spacer_count, flex: Flex::new() }
+        }).collect()
+    }
+}
@@ -0,0 +1,383 @@
+use std::{cell::{Cell, Ref, RefCell}, collections::{HashMap, HashSet}, rc::Rc};
+
+use chiropterm::*;
+use euclid::{rect, size2};
+use smallvec::SmallVec;
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetCommon, WidgetMenu, Widgetlike, layout::{Scrolls, draw_scrollbar, fix_offset}, widget::LayoutHacks};
+
+use super::{SelectionMode, TreeModel, row_selection::{RowSelection, RowWidget, bind_row_keys, selection_changed}};
+
+pub type TreeView = Widget<TreeViewState>;
+
+// Smallvec size -- set this to "deeper than most users will ever nest a tree"
+const SM: usize = 8;
+
+const INDENT: isize = 2;
+const ROW_HEIGHT: isize = 2;
+
+pub struct TreeViewState {
+    model: Option<Rc<dyn TreeModel>>,
+    expanded: HashSet<u64>,
+    children: RefCell<HashMap<u64, Vec<u64>>>,  // filled in lazily, as nodes are expanded
+    flat: RefCell<Option<Vec<FlatNode>>>,  // what's on the screen, top to bottom. None if stale
+    rows: RowSelection,
+
+    offset: Cell<f64>,
+    scroll_target: Cell<Option<usize>>,
+    viewport_rows: Cell<usize>,
+
+    pub visible_rows: usize,
+    // if set, asks for room for every row rather than `visible_rows` of them.
+    // Set this inside a Scrollable, so the tree never needs a scrollbar of its own and the Scrollable's does the scrolling
+    pub show_all_rows: bool,
+    pub on_select: Option<Box<dyn FnMut(UI, &mut WidgetCommon<TreeViewState>) -> Signal>>,
+
+    pub layout_hacks: LayoutHacks,
+}
+
+#[derive(Clone)]
+struct FlatNode {
+    node: u64,
+    has_children: bool,
+    // for each level down to this node: is that ancestor (or this node) the last of its siblings?
+    last: SmallVec<[bool; SM]>,
+}
+
+impl Scrolls for TreeViewState {
+    fn scroll_offset(&self) -> &Cell<f64> { &self.offset }
+}
+
+impl TreeViewState {
+    pub fn set_model(&mut self, model: Rc<dyn TreeModel>) {
+        self.model = Some(model);
+        self.expanded.clear();
+        self.children.replace(HashMap::new());
+        self.rows = RowSelection::new();
+        self.flat.replace(None);
+        self.offset.replace(0.0);
+    }
+
+    pub fn model(&self) -> Option<Rc<dyn TreeModel>> {
+        self.model.clone()
+    }
+
+    pub fn is_expanded(&self, node: u64) -> bool {
+        self.expanded.contains(&node)
+    }
+
+    pub fn set_expanded(&mut self, node: u64, expanded: bool) {
+        if expanded { self.expanded.insert(node); } else { self.expanded.remove(&node); }
+        self.restructure();
+    }
+
+    // Forgets the children of `node` (or every node), so they're asked for again
+    pub fn refresh(&mut self, node: Option<u64>) {
+        match node {
+            Some(n) => { self.children.borrow_mut().remove(&n); }
+            None => { self.children.borrow_mut().clear(); }
+        }
+        self.restructure();
+    }
+
+    pub fn selected(&self) -> Option<u64> {
+        let flat = self.flat();
+        let row = self.rows.selected().next()?;
+        flat.get(row).map(|f| f.node)
+    }
+
+    // Scrolls so the node's row is on screen, if it's visible at all (that is, its ancestors are expanded)
+    pub fn scroll_to(&self, node: u64) {
+        let row = self.flat().iter().position(|f| f.node == node);
+        if let Some(r) = row { self.scroll_target.replace(Some(r)); }
+    }
+
+    pub fn set_on_select(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<TreeViewState>) -> Signal) {
+        self.on_select = Some(Box::new(cmd))
+    }
+}
+
+impl Widgetlike for TreeViewState {
+    fn create() -> Self {
+        TreeViewState {
+            model: None,
+            expanded: HashSet::new(),
+            children: RefCell::new(HashMap::new()),
+            flat: RefCell::new(None),
+            rows: RowSelection::new(),
+
+            offset: Cell::new(0.0),
+            scroll_target: Cell::new(None),
+            viewport_rows: Cell::new(1),
+
+            visible_rows: 8,
+            show_all_rows: false,
+            on_select: None,
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        menu.register_focus();
+
+        let theme = menu.ui.theme().list;
+        brush.fill(FSem::new().color(theme.color));
+        brush.bevel_w95(theme.bevel);
+
+        let model = if let Some(m) = &self.model { m.clone() } else { return };
+
+        if selected {
+            bind_row_keys(&menu);
+            menu.on_key_hprio(OnKey::only(Keycode::Left).pressed(), |ui, this, _| {
+                let cursor = this.unique.rows.cursor;
+                let row = if let Some(r) = this.unique.flat().get(cursor).cloned() { r } else { return Signal::Continue };
+                if this.unique.is_expanded(row.node) {
+                    this.unique.set_expanded(row.node, false);
+                    ui.recompute_layout();
+                    return Signal::Refresh
+                }
+                // go to the parent: the nearest row above with less depth
+                let parent = this.unique.flat()[..cursor].iter().rposition(|r| r.last.len() < row.last.len());
+                match parent {
+                    Some(p) => { 
+                        this.unique.rows.move_cursor(p, false, SelectionMode::Single); 
+                        this.unique.scroll_to_row(p);
+                        selection_changed(ui, this) 
+                    }
+                    None => Signal::Continue,
+                }
+            });
+            menu.on_key_hprio(OnKey::only(Keycode::Right).pressed(), |ui, this, _| {
+                let cursor = this.unique.rows.cursor;
+                let row = if let Some(r) = this.unique.flat().get(cursor).cloned() { r } else { return Signal::Continue };
+                if !row.has_children { return Signal::Continue }
+                if !this.unique.is_expanded(row.node) {
+                    this.unique.set_expanded(row.node, true);
+                    ui.recompute_layout();
+                    return Signal::Refresh
+                }
+                let n_rows = this.unique.flat().len();
+                if cursor + 1 >= n_rows { return Signal::Continue }
+                this.unique.rows.move_cursor(cursor + 1, false, SelectionMode::Single);
+                this.unique.scroll_to_row(cursor + 1);
+                selection_changed(ui, this)
+            });
+            menu.on_key_hprio(OnKey::only(Keycode::Enter).pressed(), |ui, this, _| {
+                let cursor = this.unique.rows.cursor;
+                let row = if let Some(r) = this.unique.flat().get(cursor).cloned() { r } else { return Signal::Continue };
+                let expanded = this.unique.is_expanded(row.node);
+                this.unique.set_expanded(row.node, !expanded);
+                ui.recompute_layout();
+                Signal::Refresh
+            });
+        }
+
+        let flat = self.flat();
+        let height = brush.rect().height();
+        let inner_height = flat.len() as isize * ROW_HEIGHT;
+        let has_scrollbar = inner_height > height;
+        let width = brush.rect().width() - if has_scrollbar { 2 } else { 0 };
+        self.viewport_rows.replace((height / ROW_HEIGHT).max(1) as usize);
+
+        if let Some(target) = self.scroll_target.take() {
+            let top = target as isize * ROW_HEIGHT;
+            let bottom = top + ROW_HEIGHT;
+            let offset = self.offset.get() as isize;
+            if top < offset {
+                self.offset.replace(top as f64);
+            } else if bottom > offset + height {
+                self.offset.replace((bottom - height) as f64);
+            }
+        }
+        let offset_to_use = fix_offset(&self.offset, inner_height, height);
+
+        if has_scrollbar {
+            draw_scrollbar(brush.clone(), &menu, inner_height, offset_to_use);
+        }
+
+        // only the visible rows get drawn
+        let first = (offset_to_use / ROW_HEIGHT) as usize;
+        let last = (((offset_to_use + height + ROW_HEIGHT - 1) / ROW_HEIGHT) as usize).min(flat.len());
+        for (row, f) in flat.iter().enumerate().take(last).skip(first) {
+            let node = f.node;
+            let row_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        ui.select(this);
+                        this.unique.rows.move_cursor(row, false, SelectionMode::Single);
+                        return selection_changed(ui, this)
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+            let toggle_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        let expanded = this.unique.is_expanded(node);
+                        this.unique.set_expanded(node, !expanded);
+                        ui.recompute_layout();
+                        return Signal::Refresh
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+
+            let row_brush = brush.region(rect(0, row as isize * ROW_HEIGHT - offset_to_use, width, ROW_HEIGHT));
+            let depth = f.last.len() as isize - 1;
+
+            // indent guides: a line for every ancestor that still has siblings to come
+            for (level, last) in f.last.iter().enumerate() {
+                let x = level as isize * INDENT;
+                let glyph = if level as isize == depth {
+                    if *last { 0xc0u16 } else { 0xc3u16 }  // CP437 corner and tee
+                } else if *last { 
+                    continue 
+                } else { 
+                    0xb3u16  // CP437 vertical line
+                };
+                row_brush.region(rect(x, 0, 1, 2)).putch(glyph);
+            }
+            let x = depth * INDENT;
+            row_brush.region(rect(x + 1, 0, 1, 2)).putch(0xc4u16);  // CP437 horizontal line
+
+            let marker = row_brush.region(rect(x + 2, 0, 1, 2));
+            if f.has_children {
+                let glyph = if self.is_expanded(node) { '-' } else { '+' };
+                marker.interactor(toggle_interactor, theme.preclick).putch(glyph as u16);
+            } else {
+                marker.putch(0xc4u16);
+            }
+
+            let label_brush = row_brush.region(rect(x + 4, 0, width - x - 4, 2));
+            let color = if self.rows.is_selected(row) {
+                theme.selected
+            } else if selected && row == self.rows.cursor {
+                theme.cursor
+            } else {
+                theme.color
+            };
+            label_brush.interactor(row_interactor, theme.preclick).fill(FSem::new().color(color));
+            model.render_node(node, label_brush);
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        let model = if let Some(m) = &self.model { m.clone() } else { return InternalWidgetDimensions::zero() };
+        let flat = self.flat();
+        let width = flat.iter().map(|f| {
+            f.last.len() as isize * INDENT + 2 + model.label(f.node).chars().count() as isize
+        }).max().unwrap_or(0);
+
+        let rows = if self.show_all_rows { flat.len() } else { self.visible_rows };
+        // leave room for the scrollbar if it'll be needed
+        let scrollbar = if flat.len() > rows { 2 } else { 0 };
+        InternalWidgetDimensions { 
+            min: size2(width.min(8) + scrollbar, 2 * ROW_HEIGHT),
+            preferred: size2(width + scrollbar, rows as isize * ROW_HEIGHT),
+            max: None,
+            align_size_to: size2(1, ROW_HEIGHT),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl RowWidget for TreeViewState {
+    fn row_selection(&mut self) -> &mut RowSelection { &mut self.rows }
+
+    fn row_count(&self) -> usize { self.flat().len() }
+
+    fn page_rows(&self) -> usize { self.viewport_rows.get() }
+
+    fn selection_mode(&self) -> SelectionMode { SelectionMode::Single }
+
+    fn scroll_to_row(&self, row: usize) { self.scroll_target.replace(Some(row)); }
+
+    fn on_select(&mut self) -> &mut Option<Box<dyn FnMut(UI, &mut WidgetCommon<Self>) -> Signal>> { &mut self.on_select }
+}
+
+impl TreeViewState {
+    fn flat(&self) -> Ref<'_, Vec<FlatNode>> {
+        if self.flat.borrow().is_none() {
+            let mut out = vec![];
+            if let Some(model) = &self.model {
+                self.flatten_into(&**model, &model.roots(), &mut SmallVec::new(), &mut out);
+            }
+            self.flat.replace(Some(out));
+        }
+        Ref::map(self.flat.borrow(), |f| f.as_ref().unwrap())
+    }
+
+    // Rows move around when nodes open and close: keep the same node selected.
+    // If it's been hidden, select its nearest ancestor that's still there instead
+    fn restructure(&mut self) {
+        let lineage = self.selected_lineage();
+        self.flat.replace(None);
+        let (row, n_rows) = {
+            let flat = self.flat();
+            let row = lineage.iter().find_map(|n| flat.iter().position(|f| f.node == *n));
+            (row, flat.len())
+        };
+        match row {
+            Some(r) => {
+                self.rows.set([r]);
+                self.scroll_target.replace(Some(r));
+            }
+            None => {
+                self.rows.set(std::iter::empty());
+                self.rows.clamp(n_rows)
+            }
+        }
+    }
+
+    // The selected node, then its parent, grandparent and so on
+    fn selected_lineage(&self) -> SmallVec<[u64; SM]> {
+        let mut lineage = SmallVec::new();
+        let flat = self.flat();
+        let row = match self.rows.selected().next() {
+            Some(r) if r < flat.len() => r,
+            _ => return lineage
+        };
+
+        lineage.push(flat[row].node);
+        let mut depth = flat[row].last.len();
+        for f in flat[..row].iter().rev() {
+            if f.last.len() < depth {
+                lineage.push(f.node);
+                depth = f.last.len();
+            }
+        }
+        lineage
+    }
+
+    fn flatten_into(&self, model: &dyn TreeModel, nodes: &[u64], last: &mut SmallVec<[bool; SM]>, out: &mut Vec<FlatNode>) {
+        for (i, node) in nodes.iter().cloned().enumerate() {
+            last.push(i == nodes.len() - 1);
+            let has_children = model.has_children(node);
+            out.push(FlatNode { node, has_children, last: last.clone() });
+
+            if has_children && self.expanded.contains(&node) {
+                let children = self.children.borrow_mut()
+                    .entry(node).or_insert_with(|| model.children(node))
+                    .clone();
+                self.flatten_into(model, &children, last, out);
+            }
+            last.pop();
+        }
+    }
+}
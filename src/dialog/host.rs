@@ -0,0 +1,85 @@
+use std::cell::Cell;
+
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{AnyWidget, InternalWidgetDimensions, UI, Widget, WidgetMenu, Widgetlike, Window, widget::LayoutHacks};
+
+// The root of the tree while a dialog is open: the old root, dimmed, with the dialog centered over it
+pub(super) type DialogHost = Widget<DialogHostState>;
+
+pub(super) struct DialogHostState {
+    pub(super) backdrop: Option<(AnyWidget, CellRect)>,
+    pub(super) backdrop_ui: Option<UI>,
+    pub(super) window: Option<Window>,
+    pub(super) on_escape: Option<Box<dyn Fn() -> Signal>>,
+    focused: Cell<bool>,
+
+    layout_hacks: LayoutHacks,
+}
+
+impl Widgetlike for DialogHostState {
+    fn create() -> Self {
+        DialogHostState {
+            backdrop: None,
+            backdrop_ui: None,
+            window: None,
+            on_escape: None,
+            focused: Cell::new(false),
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        let theme = menu.ui.theme();
+        brush.fill(FSem::new().sem(SemanticContent::Blank).color(theme.base.wallpaper));
+
+        // drawn against its own UI and a menu nobody listens to, so it can't take input or focus
+        if let (Some((backdrop, at)), Some(backdrop_ui)) = (&self.backdrop, &self.backdrop_ui) {
+            let at = at.translate(-brush.cursor_offset());
+            backdrop.draw_root(backdrop_ui.share(), brush.region(at), Menu::new());
+        }
+        brush.fill(FSem::new().color(theme.dialog.backdrop));
+
+        menu.on_key(OnKey::only(Keycode::Escape).pressed(), |_, this, _| {
+            match &this.unique.on_escape {
+                Some(e) => e(),
+                None => Signal::Continue,
+            }
+        });
+
+        let window = if let Some(w) = &self.window { w } else { return };
+        let bounds = brush.rect().size;
+        let size = window.estimate_dimensions(&menu.ui, bounds.width).preferred.min(bounds);
+        let x = (bounds.width - size.width) / 2;
+        let mut y = (bounds.height - size.height) / 2;
+        y -= y % 2;
+        window.draw(menu.ui.share(), brush.region(rect(x, y, size.width, size.height)), menu.menu.share());
+
+        // start with the first thing in the dialog focused, so the keyboard works right away
+        if !self.focused.replace(true) {
+            menu.ui.focus_next();
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        // always drawn over the whole screen
+        InternalWidgetDimensions {
+            min: size2(0, 0),
+            preferred: size2(0, 0),
+            max: None,
+            align_size_to: size2(1, 1),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, ui: &UI) {
+        if let Some(w) = &self.window {
+            w.clear_layout_cache_if_needed(ui)
+        }
+    }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
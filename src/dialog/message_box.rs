@@ -0,0 +1,118 @@
+use chiropterm::Keycode;
+
+use crate::{Button, Column, InputBox, Label, Row, Spacer};
+
+use super::Dialog;
+
+pub type MessageBox = Dialog<MessageResult>;
+pub type InputDialog = Dialog<Option<String>>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MessageButtons {
+    Ok,
+    OkCancel,
+    YesNo,
+    YesNoCancel,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MessageResult {
+    Ok,
+    Cancel,
+    Yes,
+    No,
+}
+
+impl MessageButtons {
+    fn results(&self) -> &'static [MessageResult] {
+        match self {
+            MessageButtons::Ok => &[MessageResult::Ok],
+            MessageButtons::OkCancel => &[MessageResult::Ok, MessageResult::Cancel],
+            MessageButtons::YesNo => &[MessageResult::Yes, MessageResult::No],
+            MessageButtons::YesNoCancel => &[MessageResult::Yes, MessageResult::No, MessageResult::Cancel],
+        }
+    }
+}
+
+impl MessageResult {
+    fn text(&self) -> &'static str {
+        match self {
+            MessageResult::Ok => "OK",
+            MessageResult::Cancel => "Cancel",
+            MessageResult::Yes => "Yes",
+            MessageResult::No => "No",
+        }
+    }
+
+    fn hotkey(&self) -> Keycode {
+        match self {
+            MessageResult::Ok => Keycode::O,
+            MessageResult::Cancel => Keycode::C,
+            MessageResult::Yes => Keycode::Y,
+            MessageResult::No => Keycode::N,
+        }
+    }
+}
+
+impl MessageBox {
+    pub fn ask(title: impl Into<String>, text: impl Into<String>, buttons: MessageButtons) -> MessageBox {
+        let dialog = Dialog::new(title);
+        let results = buttons.results();
+        // Escape picks the least committal answer
+        dialog.set_cancel_result(*results.last().unwrap());
+
+        let text = text.into();
+        let row = Row::new().setup(|r| {
+            r.add(Spacer::new());
+            for result in results.iter().cloned() {
+                let closer = dialog.closer();
+                r.add(Button::new().setup(|b| {
+                    b.text = result.text().to_owned();
+                    b.hotkey = Some(result.hotkey());
+                    b.set_command(move |_, _, _| closer.close(result));
+                }));
+            }
+        });
+        dialog.set(Column::new().setup(|c| {
+            c.add(Label::new().setup(|l| l.set_text(text)));
+            c.add(row);
+        }));
+        dialog
+    }
+}
+
+impl InputDialog {
+    // Closes with the text the user entered, or None if they cancelled
+    pub fn ask(title: impl Into<String>, prompt: impl Into<String>, initial: impl Into<String>) -> InputDialog {
+        let dialog = Dialog::new(title);
+        dialog.set_cancel_result(None);
+
+        let prompt = prompt.into();
+        let initial = initial.into();
+        let submit = dialog.closer();
+        let field = InputBox::new().setup(|ib| {
+            ib.set_text(initial);
+            ib.set_on_submit(move |_, this| submit.close(Some(this.unique.get_text().to_owned())));
+        });
+
+        let ok = dialog.closer();
+        let cancel = dialog.closer();
+        let f = field.share();
+        dialog.set(Column::new().setup(|c| {
+            c.add(Label::new().setup(|l| l.set_text(prompt)));
+            c.add(field);
+            c.add(Row::new().setup(|r| {
+                r.add(Spacer::new());
+                r.add(Button::new().setup(|b| {
+                    b.text = "OK".to_owned();
+                    b.set_command(move |_, _, _| ok.close(Some(f.borrow().unique.get_text().to_owned())));
+                }));
+                r.add(Button::new().setup(|b| {
+                    b.text = "Cancel".to_owned();
+                    b.set_command(move |_, _, _| cancel.close(None));
+                }));
+            }));
+        }));
+        dialog
+    }
+}
@@ -0,0 +1,109 @@
+mod host;
+mod message_box;
+
+use std::{cell::RefCell, rc::Rc};
+
+use chiropterm::{IO, Signal};
+
+use crate::{AnyWidget, UI, Window};
+
+use self::host::DialogHost;
+pub use self::message_box::{InputDialog, MessageBox, MessageButtons, MessageResult};
+
+// A window shown modally over a dimmed copy of the rest of the UI, which can't be interacted with until it closes
+pub struct Dialog<R: 'static> {
+    window: Window,
+    result: Rc<RefCell<Option<R>>>,
+    cancel: Rc<RefCell<Option<Rc<dyn Fn() -> R>>>>,
+}
+
+pub struct DialogCloser<R> {
+    result: Rc<RefCell<Option<R>>>,
+}
+
+impl<R: 'static> Dialog<R> {
+    pub fn new(title: impl Into<String>) -> Dialog<R> {
+        let title = title.into();
+        Dialog {
+            window: Window::new().setup(|w| {
+                w.set_title(title);
+                w.focus_scope = true;
+            }),
+            result: Rc::new(RefCell::new(None)),
+            cancel: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    pub fn set<X: Into<AnyWidget>>(&self, content: X) {
+        self.window.setup(|w| w.set(content));
+    }
+
+    pub fn window(&self) -> Window {
+        self.window.share()
+    }
+
+    // Give this to whatever inside the dialog should close it
+    pub fn closer(&self) -> DialogCloser<R> {
+        DialogCloser { result: self.result.clone() }
+    }
+
+    // What Escape closes the dialog with. Without one, Escape does nothing
+    pub fn set_cancel_result(&self, result: R) where R: Clone {
+        self.cancel.replace(Some(Rc::new(move || result.clone())));
+    }
+
+    // Blocks until the dialog is closed. Call it from inside a Signal::Modal, or just use `run`
+    pub fn show(&self, io: &mut IO, ui: &UI) -> Option<R> {
+        self.result.replace(None);
+
+        let result = self.result.clone();
+        let cancel = self.cancel.clone();
+        let host = DialogHost::new().setup(|h| {
+            h.backdrop = ui.last_root();
+            h.backdrop_ui = Some(ui.backdrop());
+            h.window = Some(self.window.share());
+            h.on_escape = Some(Box::new(move || {
+                let c = cancel.borrow().clone();
+                match c {
+                    Some(c) => { 
+                        result.replace(Some(c())); 
+                        Signal::Break 
+                    }
+                    None => Signal::Continue
+                }
+            }));
+        });
+
+        io.menu(|out, menu| {
+            host.draw(ui.share(), out.brush(), menu)
+        });
+        self.result.borrow_mut().take()
+    }
+
+    // Use as the return value of a callback: shows the dialog, then hands its result to `on_result`
+    pub fn run(self, ui: &UI, on_result: impl 'static+FnOnce(UI, Option<R>) -> Signal) -> Signal {
+        let ui = ui.share();
+        let mut on_result = Some(on_result);
+        Signal::Modal(Box::new(move |io: &mut IO| {
+            let result = self.show(io, &ui);
+            match on_result.take() {
+                Some(f) => f(ui.share(), result),
+                None => Signal::Refresh,
+            }
+        }))
+    }
+}
+
+impl<R> DialogCloser<R> {
+    // Return this from the callback that closes the dialog
+    pub fn close(&self, result: R) -> Signal {
+        self.result.replace(Some(result));
+        Signal::Break
+    }
+}
+
+impl<R> Clone for DialogCloser<R> {
+    fn clone(&self) -> Self {
+        DialogCloser { result: self.result.clone() }
+    }
+}
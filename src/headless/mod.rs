@@ -0,0 +1,155 @@
+// Lays out and draws widgets without an IO window, for tests and for CI machines with no display.
+// Everything is drawn into an in-memory chiropterm Screen, so what you see here is what IO would show.
+
+use std::{fmt::Write, fs, path::Path};
+
+use chiropterm::*;
+use euclid::point2;
+
+use crate::{AnyWidget, Theme, UI};
+
+pub struct Headless {
+    ui: UI,
+    size: CellSize,
+}
+
+impl Headless {
+    pub fn new(theme: Theme, size: CellSize) -> Headless {
+        Headless { ui: UI::new(theme), size }
+    }
+
+    pub fn with_ui(ui: UI, size: CellSize) -> Headless {
+        Headless { ui, size }
+    }
+
+    pub fn ui(&self) -> UI {
+        self.ui.share()
+    }
+
+    pub fn resize(&mut self, size: CellSize) {
+        self.size = size;
+        self.ui.recompute_layout();
+    }
+
+    pub fn render(&self, widget: &AnyWidget) -> Snapshot {
+        let screen = Screen::new(self.size);
+        let menu = Menu::new();
+        self.draw(&screen, widget, menu.share());
+        Snapshot::capture(&screen, self.size)
+    }
+
+    pub fn key(&self, widget: &AnyWidget, key: KeyEvent) -> Option<Signal> {
+        self.send(widget, InputEvent::Keyboard(key))
+    }
+
+    pub fn mouse(&self, widget: &AnyWidget, mouse: MouseEvent) -> Option<Signal> {
+        self.send(widget, InputEvent::Mouse(mouse))
+    }
+
+    // Draws a frame, then delivers the event to whatever that frame registered -- just like IO.
+    // None means nothing in the tree handled the event.
+    pub fn send(&self, widget: &AnyWidget, event: InputEvent) -> Option<Signal> {
+        let screen = Screen::new(self.size);
+        let menu = Menu::new();
+        self.draw(&screen, widget, menu.share());
+        menu.handle(&screen, event)
+    }
+
+    fn draw<'frame>(&self, screen: &Screen, widget: &AnyWidget, menu: Menu<'frame>) {
+        screen.brush().fill(FSem::new().color(self.ui.theme().base.wallpaper));
+        widget.draw_root(self.ui.share(), screen.brush(), menu);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotCell {
+    pub glyph: u16,
+    pub fg: u8,
+    pub bg: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    size: CellSize,
+    cells: Vec<SnapshotCell>,
+}
+
+impl Snapshot {
+    fn capture(screen: &Screen, size: CellSize) -> Snapshot {
+        let mut cells = Vec::with_capacity((size.width * size.height).max(0) as usize);
+        for y in 0..size.height {
+            for x in 0..size.width {
+                let cell = screen.cell_at(point2(x, y));
+                cells.push(SnapshotCell { glyph: cell.glyph, fg: cell.fg, bg: cell.bg })
+            }
+        }
+        Snapshot { size, cells }
+    }
+
+    pub fn size(&self) -> CellSize {
+        self.size
+    }
+
+    pub fn get(&self, at: CellPoint) -> Option<SnapshotCell> {
+        if at.x < 0 || at.y < 0 || at.x >= self.size.width || at.y >= self.size.height { return None }
+        Some(self.cells[(at.y * self.size.width + at.x) as usize])
+    }
+
+    // Just the glyphs, one line of text per row of cells. Non-ASCII glyphs show as '?'
+    pub fn glyphs(&self) -> String {
+        let mut out = String::new();
+        for y in 0..self.size.height {
+            for x in 0..self.size.width {
+                let g = self.cells[(y * self.size.width + x) as usize].glyph;
+                out.push(if g >= 0x20 && g < 0x7f { g as u8 as char } else if g == 0 { ' ' } else { '?' });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    // Glyphs, then foreground, then background, as hex: stable enough to diff in a golden file
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "size {}x{}", self.size.width, self.size.height).unwrap();
+        writeln!(out, "-- glyphs").unwrap();
+        out.push_str(&self.glyphs());
+        let channels: [(&str, fn(&SnapshotCell) -> u8); 2] = [("fg", |c| c.fg), ("bg", |c| c.bg)];
+        for (name, get) in channels {
+            writeln!(out, "-- {}", name).unwrap();
+            for y in 0..self.size.height {
+                for x in 0..self.size.width {
+                    write!(out, "{:02x}", get(&self.cells[(y * self.size.width + x) as usize])).unwrap();
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    // Compares against the golden file at `path`. If CHIROPTUI_BLESS is set, writes it instead.
+    // A missing golden is a failure: otherwise a check that was never blessed would pass without checking anything
+    pub fn assert_golden(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let actual = self.to_text();
+        if std::env::var_os("CHIROPTUI_BLESS").is_some() {
+            if let Some(parent) = path.parent() { fs::create_dir_all(parent).unwrap(); }
+            fs::write(path, &actual).unwrap();
+            return
+        }
+
+        let expected = match fs::read_to_string(path) {
+            Ok(e) => e,
+            Err(_) => panic!(
+                "no golden at {} (set CHIROPTUI_BLESS=1 to create it)\n-- actual\n{}", 
+                path.display(), actual
+            ),
+        };
+        if expected != actual {
+            panic!(
+                "snapshot doesn't match {} (set CHIROPTUI_BLESS=1 to update)\n-- expected\n{}\n-- actual\n{}", 
+                path.display(), expected, actual
+            );
+        }
+    }
+}
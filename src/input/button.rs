@@ -31,7 +31,9 @@ impl Widgetlike for ButtonState {
         }
     }
 
-    fn draw<'frame>(&self, _selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        menu.register_focus();
+
         let click_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
             match click {
                 MouseEvent::Click(_, _, _) => { 
@@ -51,7 +53,18 @@ impl Widgetlike for ButtonState {
             });
         }
 
+        if selected {
+            for key in [Keycode::Enter, Keycode::Space] {
+                menu.on_key_hprio(OnKey::only(key).pressed(), move |ui, this, key| {
+                    ButtonState::click(ui, this, InputEvent::Keyboard(key))
+                });
+            }
+        }
+
         let theme = menu.ui.theme().button;
+        if selected {
+            brush.fill(FSem::new().color(theme.focused));
+        }
         brush.bevel_w95(theme.bevel);
         brush.interactor(click_interactor, theme.preclick).putfs(&self.text);
     }
@@ -0,0 +1,142 @@
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetCommon, WidgetMenu, Widgetlike, look_and_feel::ToggleTheme, widget::LayoutHacks};
+
+use super::graphemes::text_width;
+
+pub type Checkbox = Widget<CheckboxState>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckState {
+    Unchecked,
+    Checked,
+    Indeterminate,
+}
+
+pub struct CheckboxState {
+    pub hotkey: Option<Keycode>,
+    pub text: String,
+    pub state: CheckState,
+    pub on_change: Option<Box<dyn FnMut(UI, &mut WidgetCommon<CheckboxState>, CheckState) -> Signal>>,
+
+    pub layout_hacks: LayoutHacks,
+}
+
+impl CheckboxState {
+    pub fn set_on_change(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<CheckboxState>, CheckState) -> Signal) {
+        self.on_change = Some(Box::new(cmd))
+    }
+
+    pub fn is_checked(&self) -> bool {
+        self.state == CheckState::Checked
+    }
+}
+
+impl Widgetlike for CheckboxState {
+    fn create() -> Self {
+        Self {
+            hotkey: None,
+            text: "".to_owned(),
+            state: CheckState::Unchecked,
+            on_change: None,
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        menu.register_focus();
+
+        let click_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+            match click {
+                MouseEvent::Click(MouseButton::Left, _, _) => { 
+                    return CheckboxState::toggle(ui, this);
+                },
+                MouseEvent::Click(_, _, _) => {}
+                MouseEvent::Up(_, _, _) => {}
+                MouseEvent::Drag {..} => {}
+                MouseEvent::Scroll(_, _, _) => {}
+                MouseEvent::Wiggle {..} => {}
+            };
+            Signal::Continue
+        });
+
+        if let Some(hotkey) = self.hotkey {
+            menu.on_key(OnKey::only(hotkey).pressed(), move |ui, this, _| {
+                CheckboxState::toggle(ui, this)
+            });
+        }
+
+        if selected {
+            menu.on_key_hprio(OnKey::only(Keycode::Space).pressed(), move |ui, this, _| {
+                CheckboxState::toggle(ui, this)
+            });
+        }
+
+        let theme = menu.ui.theme().checkbox;
+        let mark = match self.state {
+            CheckState::Unchecked => None,
+            CheckState::Checked => Some(0xfbu16),  // CP437 check mark
+            CheckState::Indeterminate => Some(0xc4u16),  // CP437 horizontal bar
+        };
+        draw_toggle(brush, click_interactor, theme, selected, mark, &self.text);
+    }
+
+    fn estimate_dimensions(&self, _ui: &UI, width: isize) -> InternalWidgetDimensions {
+        estimate_toggle(&self.text, width)
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl CheckboxState {
+    fn toggle(ui: UI, this: &mut WidgetCommon<Self>) -> Signal {
+        ui.select(this);
+        this.unique.state = match this.unique.state {
+            CheckState::Checked => CheckState::Unchecked,
+            CheckState::Unchecked | CheckState::Indeterminate => CheckState::Checked,
+        };
+
+        let on_change = this.unique.on_change.take();
+        if let Some(mut c) = on_change {
+            let state = this.unique.state;
+            let result = c(ui, this, state);
+            this.unique.on_change.replace(c);
+            return result
+        }
+        Signal::Refresh
+    }
+}
+
+// Shared by checkboxes and radio buttons: a small box with a glyph in it, then the text
+pub(crate) fn draw_toggle(brush: Brush, interactor: Interactor, theme: ToggleTheme, selected: bool, mark: Option<u16>, text: &str) {
+    let box_brush = brush.region(rect(0, 0, 3, 2));
+    box_brush.interactor(interactor, theme.preclick).fill(FSem::new().color(theme.box_color));
+    box_brush.bevel_w95(theme.bevel);
+    if let Some(m) = mark {
+        box_brush.region(rect(1, 0, 1, 2)).interactor(interactor, theme.preclick).putch(m);
+    }
+
+    let text_brush = brush.region(rect(4, 0, (brush.rect().width() - 4).max(0), brush.rect().height()));
+    if selected {
+        text_brush.fill(FSem::new().color(theme.focused));
+    }
+    text_brush.interactor(interactor, theme.preclick).putfs(text);
+}
+
+pub(crate) fn estimate_toggle(text: &str, width: isize) -> InternalWidgetDimensions {
+    let stamp = Stamp::new();
+    let brush = stamp.brush_at(rect(0, 0, (width - 4).max(0), isize::MAX));
+    brush.putfs(text);
+    InternalWidgetDimensions {
+        min: size2(4 + 8.min(text_width(text) as isize), 2),
+        preferred: size2(4 + stamp.rect().size.width, stamp.rect().size.height.max(2)),
+        max: None,
+        align_size_to: size2(1, 2),
+        horizontal_spacer_count: 0,
+        vertical_spacer_count: 0,
+    }
+}
@@ -0,0 +1,346 @@
+use std::time::{Duration, Instant};
+
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{InternalWidgetDimensions, OverlayOptions, UI, Widget, WidgetCommon, Widgetlike, widget::{LayoutHacks, WidgetMenu}};
+
+use super::{InputBox, graphemes::text_width};
+
+pub type ComboBox = Widget<ComboBoxState>;
+
+// Type-ahead keystrokes further apart than this start a new search
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+pub struct ComboBoxState {
+    items: Vec<String>,
+    selected: Option<usize>,
+    field: InputBox,  // only drawn if the combo box is editable
+
+    open: bool,
+    highlighted: usize,
+    scroll: usize,
+    type_ahead: (String, Option<Instant>),
+
+    pub editable: bool,
+    pub max_width: Option<usize>,
+    pub max_visible_items: usize,
+    pub on_change: Option<Box<dyn FnMut(UI, &mut WidgetCommon<ComboBoxState>) -> Signal>>,
+
+    pub layout_hacks: LayoutHacks,
+}
+
+impl ComboBoxState {
+    pub fn add_item(&mut self, item: impl Into<String>) {
+        self.items.push(item.into())
+    }
+
+    pub fn set_items<S: Into<String>>(&mut self, items: impl IntoIterator<Item=S>) {
+        self.items = items.into_iter().map(|i| i.into()).collect();
+        self.set_selected(None);
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn set_selected(&mut self, selected: Option<usize>) {
+        self.selected = selected.filter(|s| *s < self.items.len());
+        let text = self.selected.map(|s| self.items[s].clone()).unwrap_or_default();
+        self.field.setup(|f| f.set_text(text));
+        self.highlighted = self.selected.unwrap_or(0);
+        self.scroll_to_highlighted();
+    }
+
+    // The chosen item, or whatever the user typed if the combo box is editable
+    pub fn text(&self) -> String {
+        if self.editable {
+            self.field.borrow().unique.get_text().to_owned()
+        } else {
+            self.selected.map(|s| self.items[s].clone()).unwrap_or_default()
+        }
+    }
+
+    pub fn set_on_change(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<ComboBoxState>) -> Signal) {
+        self.on_change = Some(Box::new(cmd))
+    }
+}
+
+impl Widgetlike for ComboBoxState {
+    fn create() -> Self {
+        Self {
+            items: vec![],
+            selected: None,
+            field: InputBox::new(),
+
+            open: false,
+            highlighted: 0,
+            scroll: 0,
+            type_ahead: (String::new(), None),
+
+            editable: false,
+            max_width: None,
+            max_visible_items: 8,
+            on_change: None,
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        // editable combo boxes take focus through their text field
+        if !self.editable { menu.register_focus(); }
+        let focused = selected || (self.editable && menu.ui.is_selected(self.field.borrow().selection));
+
+        if focused {
+            menu.on_key_hprio(OnKey::only(Keycode::Down).pressed(), |ui, this, _| {
+                if this.unique.open || this.unique.editable {
+                    this.unique.open = true;
+                    this.unique.move_highlight(1);
+                    Signal::Refresh
+                } else {
+                    let next = this.unique.selected.map(|s| s + 1).unwrap_or(0);
+                    if next >= this.unique.items.len() { return Signal::Continue }
+                    ComboBoxState::choose(ui, this, next)
+                }
+            });
+            menu.on_key_hprio(OnKey::only(Keycode::Up).pressed(), |ui, this, _| {
+                if this.unique.open {
+                    this.unique.move_highlight(-1);
+                    Signal::Refresh
+                } else {
+                    match this.unique.selected {
+                        Some(s) if s > 0 && !this.unique.editable => ComboBoxState::choose(ui, this, s - 1),
+                        _ => Signal::Continue,
+                    }
+                }
+            });
+
+            if self.open {
+                menu.on_key_hprio(OnKey::only(Keycode::Enter).pressed(), |ui, this, _| {
+                    let h = this.unique.highlighted;
+                    if h >= this.unique.items.len() { return Signal::Continue }
+                    ComboBoxState::choose(ui, this, h)
+                });
+                menu.on_key_hprio(OnKey::only(Keycode::Escape).pressed(), |_, this, _| {
+                    this.unique.open = false;
+                    Signal::Refresh
+                });
+            } else if !self.editable {
+                menu.on_key_hprio(OnKey::only(Keycode::Enter).pressed(), |_, this, _| {
+                    this.unique.toggle();
+                    Signal::Refresh
+                });
+            }
+        }
+
+        if selected && !self.editable {
+            menu.on_text_hprio(|ui, this, character| {
+                match this.unique.type_ahead(character) {
+                    Some(i) if this.unique.open => { 
+                        this.unique.highlighted = i; 
+                        this.unique.scroll_to_highlighted(); 
+                        Signal::Refresh 
+                    }
+                    Some(i) => ComboBoxState::choose(ui, this, i),
+                    None => Signal::Refresh,
+                }
+            });
+        }
+
+        let toggle_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+            match click {
+                MouseEvent::Click(MouseButton::Left, _, _) => {
+                    if !this.unique.editable { ui.select(this); }
+                    this.unique.toggle();
+                    return Signal::Refresh
+                },
+                MouseEvent::Click(_, _, _) => {}
+                MouseEvent::Up(_, _, _) => {}
+                MouseEvent::Drag {..} => {}
+                MouseEvent::Scroll(_, _, _) => {}
+                MouseEvent::Wiggle {..} => {}
+            };
+            Signal::Continue
+        });
+
+        let theme = menu.ui.theme();
+        let width = brush.rect().width();
+        let field_brush = brush.region(rect(0, 0, width - 2, 2));
+        let arrow_brush = brush.region(rect(width - 2, 0, 2, 2));
+
+        if self.editable {
+            self.field.draw(menu.ui.share(), field_brush, menu.menu.share());
+        } else {
+            field_brush.fill(FSem::new().color(if selected { theme.input_box.cursor } else { theme.input_box.deselected }));
+            field_brush.bevel_w95(theme.input_box.bevel);
+            field_brush.interactor(toggle_interactor, theme.input_box.preclick).putfs(&self.text());
+        }
+
+        arrow_brush.bevel_w95(theme.button.bevel);
+        arrow_brush.interactor(toggle_interactor, theme.button.preclick).font(Font::Set).putch(0x1fu16);
+
+        if self.open && self.items.len() > 0 {
+            let n_visible = self.items.len().min(self.max_visible_items.max(1));
+            let o = brush.cursor_offset();
+            let combo = menu.widget();
+            menu.ui.push_overlay_with(
+                rect(o.x, o.y + 2, width, n_visible as isize * 2),
+                ComboList::new().setup(|l| l.owner = Some(menu.widget())),
+                OverlayOptions::new().dismiss_on_outside_click(move |_| {
+                    combo.borrow_mut().unique.open = false;
+                    Signal::Refresh
+                }),
+            );
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        let mut preferred_w;
+        let mut max = size2(isize::MAX, 2);
+        if let Some(mx) = self.max_width {
+            preferred_w = mx as isize;
+            max.width = preferred_w;
+        } else {
+            preferred_w = self.items.iter().map(|i| text_width(i)).max().unwrap_or(0) as isize + 2;
+            preferred_w = preferred_w.max(10);
+        }
+
+        InternalWidgetDimensions { 
+            min: size2(preferred_w, 2),
+            preferred: size2(preferred_w, 2),
+            max: Some(max),
+            align_size_to: size2(1, 2),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, ui: &UI) { 
+        self.field.clear_layout_cache_if_needed(ui)
+    }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl ComboBoxState {
+    fn choose(ui: UI, this: &mut WidgetCommon<Self>, i: usize) -> Signal {
+        let changed = this.unique.selected != Some(i) || this.unique.editable;
+        this.unique.set_selected(Some(i));
+        this.unique.open = false;
+        if !changed { return Signal::Refresh }
+
+        let on_change = this.unique.on_change.take();
+        if let Some(mut c) = on_change {
+            let result = c(ui, this);
+            this.unique.on_change.replace(c);
+            return result
+        }
+        Signal::Refresh
+    }
+
+    fn toggle(&mut self) {
+        self.open = !self.open;
+        if self.open {
+            self.highlighted = self.selected.unwrap_or(0);
+            self.scroll_to_highlighted();
+        }
+    }
+
+    fn move_highlight(&mut self, amount: isize) {
+        if self.items.len() == 0 { return }
+        let h = (self.highlighted as isize + amount).max(0).min(self.items.len() as isize - 1);
+        self.highlighted = h as usize;
+        self.scroll_to_highlighted();
+    }
+
+    fn scroll_to_highlighted(&mut self) {
+        let n_visible = self.max_visible_items.max(1);
+        if self.highlighted < self.scroll {
+            self.scroll = self.highlighted;
+        } else if self.highlighted >= self.scroll + n_visible {
+            self.scroll = self.highlighted + 1 - n_visible;
+        }
+    }
+
+    fn type_ahead(&mut self, character: char) -> Option<usize> {
+        let now = Instant::now();
+        if let Some(last) = self.type_ahead.1 {
+            if now.duration_since(last) > TYPE_AHEAD_TIMEOUT { self.type_ahead.0.clear(); }
+        }
+        self.type_ahead.1 = Some(now);
+        self.type_ahead.0.extend(character.to_lowercase());
+
+        let prefix = &self.type_ahead.0;
+        self.items.iter().position(|i| i.to_lowercase().starts_with(prefix.as_str()))
+    }
+}
+
+// The popup half of a combo box. It's created fresh every frame that the combo box is open
+type ComboList = Widget<ComboListState>;
+
+struct ComboListState {
+    owner: Option<ComboBox>,
+}
+
+impl Widgetlike for ComboListState {
+    fn create() -> Self {
+        ComboListState { owner: None }
+    }
+
+    fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        let owner = if let Some(o) = &self.owner { o.borrow() } else { return };
+        let combo = &owner.unique;
+
+        let theme = menu.ui.theme().input_box;
+        brush.fill(FSem::new().color(theme.deselected));
+        brush.bevel_w95(theme.bevel);
+
+        let n_visible = combo.max_visible_items.max(1);
+        for (row, i) in (combo.scroll..combo.items.len()).take(n_visible).enumerate() {
+            let interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+                let owner = if let Some(o) = &this.unique.owner { o.share() } else { return Signal::Continue };
+                let mut combo = owner.borrow_mut();
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        return ComboBoxState::choose(ui, &mut *combo, i)
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(amt, _, _) => {
+                        let n_items = combo.unique.items.len();
+                        let max_scroll = n_items.saturating_sub(combo.unique.max_visible_items.max(1));
+                        let scroll = combo.unique.scroll as isize + amt.signum() as isize;
+                        combo.unique.scroll = scroll.max(0).min(max_scroll as isize) as usize;
+                        return Signal::Refresh
+                    }
+                    MouseEvent::Wiggle {..} => {
+                        combo.unique.highlighted = i;
+                        return Signal::Refresh
+                    }
+                };
+                Signal::Continue
+            });
+
+            let row_brush = brush.region(rect(0, row as isize * 2, brush.rect().width(), 2));
+            if i == combo.highlighted {
+                row_brush.fill(FSem::new().color(theme.cursor));
+            }
+            row_brush.interactor(interactor, theme.preclick).putfs(&combo.items[i]);
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        // the combo box decides where the popup goes
+        InternalWidgetDimensions::zero()
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { LayoutHacks::new() }
+}
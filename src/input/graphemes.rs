@@ -0,0 +1,129 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Cursors are byte offsets that always sit between grapheme clusters.
+// Each cluster is drawn in cells of its own, as many as its display width: two for most CJK, otherwise one
+
+pub(crate) fn cluster_width(cluster: &str) -> usize {
+    // zero-width clusters (ex. a stray joiner) still get a cell, or nothing could click on them
+    cluster.width().max(1)
+}
+
+pub(crate) fn text_width(text: &str) -> usize {
+    text.graphemes(true).map(cluster_width).sum()
+}
+
+pub(crate) fn column_of(text: &str, ix: usize) -> usize {
+    text_width(&text[..ix])
+}
+
+// The byte range of the cluster drawn at this column, or an empty range at the end of the text
+pub(crate) fn grapheme_at_column(text: &str, x: usize) -> Range<usize> {
+    let mut column = 0;
+    for (i, g) in text.grapheme_indices(true) {
+        let width = cluster_width(g);
+        if x < column + width { return i..i + g.len() }
+        column += width;
+    }
+    text.len()..text.len()
+}
+
+pub(crate) fn next_boundary(text: &str, ix: usize) -> usize {
+    text[ix..].grapheme_indices(true).nth(1).map(|(i, _)| ix + i).unwrap_or(text.len())
+}
+
+pub(crate) fn prev_boundary(text: &str, ix: usize) -> usize {
+    text[..ix].grapheme_indices(true).next_back().map(|(i, _)| i).unwrap_or(0)
+}
+
+pub(crate) fn snap_to_boundary(text: &str, ix: usize) -> usize {
+    if ix >= text.len() { return text.len() }
+    text.grapheme_indices(true).map(|(i, _)| i).take_while(|i| *i <= ix).last().unwrap_or(0)
+}
+
+// Start of the word before the caret, skipping any spaces in between
+pub(crate) fn prev_word(text: &str, ix: usize) -> usize {
+    text[..ix].split_word_bound_indices()
+        .rev()
+        .find(|(_, w)| !w.trim().is_empty())
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+// Start of the next word, as on Windows: past the rest of this word, then past any spaces
+pub(crate) fn next_word(text: &str, ix: usize) -> usize {
+    let mut segments = text[ix..].split_word_bound_indices().peekable();
+    if let Some((_, w)) = segments.peek() {
+        if !w.trim().is_empty() { segments.next(); }
+    }
+    segments
+        .find(|(_, w)| !w.trim().is_empty())
+        .map(|(i, _)| ix + i)
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "e" plus a combining acute accent: two chars, one cluster
+    const E_ACUTE: &str = "e\u{301}";
+
+    #[test]
+    fn boundaries_step_over_whole_clusters() {
+        let text = format!("a{}b", E_ACUTE);
+        assert_eq!(next_boundary(&text, 1), 4);
+        assert_eq!(prev_boundary(&text, 4), 1);
+        assert_eq!(next_boundary(&text, text.len()), text.len());
+        assert_eq!(prev_boundary(&text, 0), 0);
+    }
+
+    #[test]
+    fn snapping_lands_on_the_start_of_a_cluster() {
+        let text = format!("a{}b", E_ACUTE);
+        assert_eq!(snap_to_boundary(&text, 2), 1);
+        assert_eq!(snap_to_boundary(&text, 4), 4);
+        assert_eq!(snap_to_boundary(&text, 99), text.len());
+    }
+
+    #[test]
+    fn columns_follow_display_width() {
+        let text = format!("a{}漢字b", E_ACUTE);
+        assert_eq!(text_width(&text), 1 + 1 + 2 + 2 + 1);
+        assert_eq!(column_of(&text, 1), 1);
+        assert_eq!(column_of(&text, 4), 2);
+        assert_eq!(column_of(&text, 7), 4);
+        assert_eq!(column_of(&text, text.len()), 7);
+    }
+
+    #[test]
+    fn both_columns_of_a_wide_cluster_hit_it() {
+        let text = "a漢b";
+        assert_eq!(grapheme_at_column(text, 0), 0..1);
+        assert_eq!(grapheme_at_column(text, 1), 1..4);
+        assert_eq!(grapheme_at_column(text, 2), 1..4);
+        assert_eq!(grapheme_at_column(text, 3), 4..5);
+        assert_eq!(grapheme_at_column(text, 4), 5..5);
+    }
+
+    #[test]
+    fn word_motion_skips_spaces() {
+        let text = "one two  three";
+        assert_eq!(next_word(text, 0), 4);
+        assert_eq!(next_word(text, 4), 9);
+        assert_eq!(next_word(text, 9), text.len());
+        assert_eq!(prev_word(text, text.len()), 9);
+        assert_eq!(prev_word(text, 9), 4);
+        assert_eq!(prev_word(text, 5), 4);
+        assert_eq!(prev_word(text, 0), 0);
+    }
+
+    #[test]
+    fn word_motion_from_inside_spaces() {
+        let text = "one   two";
+        assert_eq!(next_word(text, 4), 6);
+        assert_eq!(prev_word(text, 5), 0);
+    }
+}
@@ -0,0 +1,301 @@
+use std::ops::Range;
+
+// Oldest steps fall off the bottom past this
+const MAX_UNDO: usize = 200;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EditKind { Typing, Deleting, Other }
+
+// Text that undo can cut up and splice back together.
+// Positions count its units: bytes of a String, lines of a Vec<String>
+pub(crate) trait Piece: Sized {
+    fn len(&self) -> usize;
+    fn slice(&self, range: Range<usize>) -> Self;
+    fn splice(&mut self, at: usize, len: usize, with: &Self);
+    // How many units at the start and at the end the two have in common
+    fn common_ends(&self, other: &Self) -> (usize, usize);
+}
+
+impl Piece for String {
+    fn len(&self) -> usize { self.len() }
+    fn slice(&self, range: Range<usize>) -> String { self[range].to_owned() }
+    fn splice(&mut self, at: usize, len: usize, with: &String) { self.replace_range(at..at + len, with) }
+
+    fn common_ends(&self, other: &String) -> (usize, usize) {
+        // count in chars, so neither end splits one
+        let head = self.char_indices().zip(other.chars())
+            .find(|((_, a), b)| a != b)
+            .map(|((i, _), _)| i)
+            .unwrap_or(self.len().min(other.len()));
+        let room = self.len().min(other.len()) - head;
+        let tail: usize = self[head..].chars().rev().zip(other[head..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .scan(0, |total, l| { *total += l; Some(*total) })
+            .take_while(|total| *total <= room)
+            .last().unwrap_or(0);
+        (head, tail)
+    }
+}
+
+impl Piece for Vec<String> {
+    fn len(&self) -> usize { self.len() }
+    fn slice(&self, range: Range<usize>) -> Vec<String> { self[range].to_vec() }
+    fn splice(&mut self, at: usize, len: usize, with: &Vec<String>) { Vec::splice(self, at..at + len, with.iter().cloned()); }
+
+    fn common_ends(&self, other: &Vec<String>) -> (usize, usize) {
+        let head = self.iter().zip(other.iter()).take_while(|(a, b)| a == b).count();
+        let room = self.len().min(other.len()) - head;
+        let tail = self.iter().rev().zip(other.iter().rev()).take_while(|(a, b)| a == b).count().min(room);
+        (head, tail)
+    }
+}
+
+// One step: at `at`, `removed` was replaced by `inserted`.
+// C is whatever else undo puts back, ex. the caret
+struct Change<P, C> {
+    at: usize,
+    removed: P,
+    inserted: P,
+    before: C,
+    after: C,
+}
+
+impl<P: Piece, C: Copy> Change<P, C> {
+    // Takes in `next` if it edits the same stretch of text this one left behind, or right next to it
+    fn absorb(&mut self, next: &Change<P, C>) -> bool {
+        let (a, i) = (self.at, self.inserted.len());
+        let (b, r) = (next.at, next.removed.len());
+        if b > a + i || b + r < a { return false }
+
+        // widen this step to take in whatever `next` removed on either side of it
+        let start = a.min(b);
+        if b < a {
+            let head = next.removed.slice(0..a - b);
+            self.removed.splice(0, 0, &head);
+            self.inserted.splice(0, 0, &head);
+        }
+        if b + r > a + i {
+            let tail = next.removed.slice(a + i - b..r);
+            self.removed.splice(self.removed.len(), 0, &tail);
+            self.inserted.splice(self.inserted.len(), 0, &tail);
+        }
+
+        self.inserted.splice(b - start, r, &next.inserted);
+        self.at = start;
+        self.after = next.after;
+        true
+    }
+}
+
+// Undo/redo for a text widget, as the changes made to its text
+pub(crate) struct History<P, C> {
+    undo: Vec<Change<P, C>>,
+    redo: Vec<Change<P, C>>,
+    coalescing: Option<EditKind>,  // the kind of the latest edit, if the next one can join its step
+}
+
+impl<P: Piece, C: Copy+PartialEq> History<P, C> {
+    pub(crate) fn new() -> History<P, C> {
+        History { undo: vec![], redo: vec![], coalescing: None }
+    }
+
+    // `before` is the text starting at `at` that the edit might have touched, `after` what's there now.
+    // Returns false if they're the same, in which case nothing is recorded
+    pub(crate) fn record(&mut self, at: usize, before: P, after: &P, carets: (C, C), kind: EditKind) -> bool {
+        let (head, tail) = before.common_ends(after);
+        if head == before.len() && head == after.len() { return false }
+
+        let change = Change {
+            at: at + head,
+            removed: before.slice(head..before.len() - tail),
+            inserted: after.slice(head..after.len() - tail),
+            before: carets.0,
+            after: carets.1,
+        };
+        self.redo.clear();
+
+        // a run of typing (or deleting) is one step, unless the caret went somewhere else in between
+        // (the diff can land a little way from the caret in a run of the same character, so that can still fail)
+        let joins = self.coalescing == Some(kind) && kind != EditKind::Other;
+        self.coalescing = Some(kind);
+        if let Some(last) = self.undo.last_mut() {
+            if joins && last.after == change.before && last.absorb(&change) { return true }
+        }
+        self.undo.push(change);
+        if self.undo.len() > MAX_UNDO { self.undo.remove(0); }
+        true
+    }
+
+    // Puts `text` back how it was before the latest step and returns what else to restore
+    pub(crate) fn undo(&mut self, text: &mut P) -> Option<C> {
+        let change = self.undo.pop()?;
+        text.splice(change.at, change.inserted.len(), &change.removed);
+        let before = change.before;
+        self.redo.push(change);
+        self.coalescing = None;
+        Some(before)
+    }
+
+    pub(crate) fn redo(&mut self, text: &mut P) -> Option<C> {
+        let change = self.redo.pop()?;
+        text.splice(change.at, change.removed.len(), &change.inserted);
+        let after = change.after;
+        self.undo.push(change);
+        self.coalescing = None;
+        Some(after)
+    }
+
+    // Ends the current step, so the next edit starts one of its own
+    pub(crate) fn seal(&mut self) {
+        self.coalescing = None;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.coalescing = None;
+    }
+
+    pub(crate) fn can_undo(&self) -> bool { !self.undo.is_empty() }
+    pub(crate) fn can_redo(&self) -> bool { !self.redo.is_empty() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A one-line editor with just enough to drive History: the caret is the only thing besides the text
+    struct Editor { text: String, caret: usize, history: History<String, usize> }
+
+    impl Editor {
+        fn new(text: &str) -> Editor {
+            Editor { text: text.to_owned(), caret: text.len(), history: History::new() }
+        }
+
+        fn edit(&mut self, kind: EditKind, f: impl FnOnce(&mut String, &mut usize)) {
+            let (before, caret) = (self.text.clone(), self.caret);
+            f(&mut self.text, &mut self.caret);
+            self.history.record(0, before, &self.text, (caret, self.caret), kind);
+        }
+
+        fn type_str(&mut self, s: &str) {
+            for c in s.chars() {
+                self.edit(EditKind::Typing, |t, caret| { t.insert(*caret, c); *caret += c.len_utf8() })
+            }
+        }
+
+        fn backspace(&mut self) {
+            self.edit(EditKind::Deleting, |t, caret| { *caret -= 1; t.remove(*caret); })
+        }
+
+        fn undo(&mut self) { if let Some(c) = self.history.undo(&mut self.text) { self.caret = c } }
+        fn redo(&mut self) { if let Some(c) = self.history.redo(&mut self.text) { self.caret = c } }
+    }
+
+    #[test]
+    fn a_run_of_typing_is_one_step() {
+        let mut e = Editor::new("");
+        e.type_str("hello");
+        e.undo();
+        assert_eq!((e.text.as_str(), e.caret), ("", 0));
+        assert!(!e.history.can_undo());
+        e.redo();
+        assert_eq!((e.text.as_str(), e.caret), ("hello", 5));
+    }
+
+    #[test]
+    fn a_run_of_backspaces_is_one_step() {
+        let mut e = Editor::new("hello");
+        e.backspace();
+        e.backspace();
+        e.undo();
+        assert_eq!((e.text.as_str(), e.caret), ("hello", 5));
+        assert!(!e.history.can_undo());
+    }
+
+    #[test]
+    fn moving_the_caret_breaks_coalescing() {
+        let mut e = Editor::new("");
+        e.type_str("ab");
+        e.caret = 0;
+        e.type_str("x");
+        assert_eq!(e.text, "xab");
+        e.undo();
+        assert_eq!(e.text, "ab");
+        e.undo();
+        assert_eq!(e.text, "");
+    }
+
+    #[test]
+    fn typing_and_deleting_are_separate_steps() {
+        let mut e = Editor::new("");
+        e.type_str("abc");
+        e.backspace();
+        e.undo();
+        assert_eq!(e.text, "abc");
+    }
+
+    #[test]
+    fn sealing_starts_a_new_step() {
+        let mut e = Editor::new("");
+        e.type_str("ab");
+        e.history.seal();
+        e.type_str("cd");
+        e.undo();
+        assert_eq!(e.text, "ab");
+    }
+
+    #[test]
+    fn a_new_edit_clears_redo() {
+        let mut e = Editor::new("");
+        e.type_str("a");
+        e.undo();
+        assert!(e.history.can_redo());
+        e.type_str("b");
+        assert!(!e.history.can_redo());
+        e.redo();
+        assert_eq!(e.text, "b");
+    }
+
+    #[test]
+    fn oldest_steps_fall_off_past_max_undo() {
+        let mut e = Editor::new("");
+        for _ in 0..MAX_UNDO + 5 {
+            e.history.seal();
+            e.type_str("x");
+        }
+        let mut steps = 0;
+        while e.history.can_undo() {
+            e.undo();
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_UNDO);
+        assert_eq!(e.text, "xxxxx");
+    }
+
+    #[test]
+    fn string_ends_never_split_a_char() {
+        // é and è share their first byte
+        assert_eq!("aé".to_owned().common_ends(&"aè".to_owned()), (1, 0));
+        assert_eq!("éa".to_owned().common_ends(&"èa".to_owned()), (0, 1));
+        assert_eq!("aa".to_owned().common_ends(&"aaa".to_owned()), (2, 0));
+    }
+
+    #[test]
+    fn lines_round_trip() {
+        let lines = |ls: &[&str]| ls.iter().map(|l| l.to_string()).collect::<Vec<String>>();
+        let mut text = lines(&["one", "two", "three"]);
+        let mut history: History<Vec<String>, usize> = History::new();
+
+        // join "two" onto "one", as a backspace at the start of a line would
+        let before = text[0..=2].to_vec();
+        text = lines(&["onetwo", "three"]);
+        history.record(0, before, &text, (0, 0), EditKind::Deleting);
+
+        history.undo(&mut text);
+        assert_eq!(text, lines(&["one", "two", "three"]));
+        history.redo(&mut text);
+        assert_eq!(text, lines(&["onetwo", "three"]));
+    }
+}
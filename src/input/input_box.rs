@@ -1,16 +1,44 @@
+use std::{cell::Cell, ops::Range, time::{Duration, Instant}};
+
 use chiropterm::*;
 use euclid::{rect, size2};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Accelerator, InternalWidgetDimensions, MenuItem, PopupMenu, UI, Widget, WidgetCommon, Widgetlike, widget::{LayoutHacks, WidgetMenu}};
 
-use crate::{InternalWidgetDimensions, UI, Widget, Widgetlike, widget::{LayoutHacks, WidgetMenu}};
+use super::{graphemes::{cluster_width, column_of, grapheme_at_column, next_boundary, next_word, prev_boundary, prev_word, snap_to_boundary, text_width}, history::{EditKind, History}};
 
 pub type InputBox = Widget<InputBoxState>;
 
+// What a validator thinks of the text after an edit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Validation {
+    Valid,
+    // keep the edit, but show the field in the error color and don't submit it
+    Invalid,
+    // undo the edit, as if the key was never pressed
+    Reject,
+}
+
+// A second click on the same column sooner than this selects the word under it
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+
 pub struct InputBoxState {
     text: String,
-    cursor_l: usize,
-    cursor_r: usize,
+    // byte offsets, always between grapheme clusters. the selection runs between them
+    caret: usize,
+    anchor: usize,
+    scroll: Cell<usize>,  // first visible column
+    last_click: Option<(Instant, usize)>,
+    history: History<String, (usize, usize)>,  // carets are (caret, anchor)
+    revision: u64,  // bumped by every edit, so handlers can tell whether to run on_change
+    invalid: bool,
     pub max_width: Option<usize>,
-    // TODO: Store left position of window
+
+    pub validator: Option<Box<dyn Fn(&str) -> Validation>>,
+    pub on_change: Option<Box<dyn FnMut(UI, &mut WidgetCommon<InputBoxState>) -> Signal>>,
+    pub on_submit: Option<Box<dyn FnMut(UI, &mut WidgetCommon<InputBoxState>) -> Signal>>,
+    pub on_cancel: Option<Box<dyn FnMut(UI, &mut WidgetCommon<InputBoxState>) -> Signal>>,
 
     pub layout_hacks: LayoutHacks,
 }
@@ -19,55 +47,98 @@ impl Widgetlike for InputBoxState {
     fn create() -> Self {
         Self { 
             text: "".to_owned(),
-            cursor_l: 0,
-            cursor_r: 0,
+            caret: 0,
+            anchor: 0,
+            scroll: Cell::new(0),
+            last_click: None,
+            history: History::new(),
+            revision: 0,
+            invalid: false,
             max_width: None,
 
+            validator: None,
+            on_change: None,
+            on_submit: None,
+            on_cancel: None,
+
             layout_hacks: LayoutHacks::new(),
         }
     }
 
     fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, InputBoxState>) {
+        menu.register_focus();
+
         if selected {
-            menu.on_text_hprio( |_, this, character| { this.unique.type_character(character); Signal::Refresh });
+            menu.on_text_hprio( |ui, this, character| { 
+                InputBoxState::handle(ui, this, |s, _| s.edit(EditKind::Typing, |s| s.type_character(character)))
+            });
             menu.on_key_hprio( 
                 OnKey::only(Keycode::Backspace).pressed(),
-                |_, this, _| {this.unique.backspace(); Signal::Refresh }
+                |ui, this, _| InputBoxState::handle(ui, this, |s, _| s.edit(EditKind::Deleting, InputBoxState::backspace))
             );
             menu.on_key_hprio( 
                 OnKey::only(Keycode::Delete).pressed(),
-                |_, this, _| {this.unique.delete(); Signal::Refresh }
-            );
-            menu.on_key_hprio( 
-                OnKey::only(Keycode::Left).pressed(),
-                |_, this, _| {this.unique.move_cursor(-1); Signal::Refresh }
+                |ui, this, _| InputBoxState::handle(ui, this, |s, _| s.edit(EditKind::Deleting, InputBoxState::delete))
             );
             menu.on_key_hprio( 
-                OnKey::only(Keycode::Right).pressed(), 
-                |_, this, _| {this.unique.move_cursor(1); Signal::Refresh }
+                OnKey::only(Keycode::Backspace).control().pressed(),
+                |ui, this, _| InputBoxState::handle(ui, this, |s, _| s.edit(EditKind::Other, InputBoxState::backspace_word))
             );
             menu.on_key_hprio( 
-                OnKey::only(Keycode::Home).pressed(), 
-                |_, this, _| {this.unique.set_cursor(0); Signal::Refresh }
+                OnKey::only(Keycode::Delete).control().pressed(),
+                |ui, this, _| InputBoxState::handle(ui, this, |s, _| s.edit(EditKind::Other, InputBoxState::delete_word))
             );
-            menu.on_key_hprio( 
-                OnKey::only(Keycode::End).pressed(), 
-                |_, this, _| {this.unique.set_cursor(this.unique.text.len()); Signal::Refresh }
+            bind_motion(&menu, Keycode::Left, false, InputBoxState::move_left);
+            bind_motion(&menu, Keycode::Right, false, InputBoxState::move_right);
+            bind_motion(&menu, Keycode::Left, true, |this, extend| this.move_to(prev_word(&this.text, this.caret), extend));
+            bind_motion(&menu, Keycode::Right, true, |this, extend| this.move_to(next_word(&this.text, this.caret), extend));
+            bind_motion(&menu, Keycode::Home, false, |this, extend| this.move_to(0, extend));
+            bind_motion(&menu, Keycode::End, false, |this, extend| this.move_to(this.text.len(), extend));
+            bind_edit(&menu, Keycode::X, InputBoxState::cut);
+            bind_edit(&menu, Keycode::C, InputBoxState::copy);
+            bind_edit(&menu, Keycode::V, InputBoxState::paste);
+            bind_edit(&menu, Keycode::A, |s, _| s.select_all());
+            bind_edit(&menu, Keycode::Z, |s, _| s.undo());
+            bind_edit(&menu, Keycode::Y, |s, _| s.redo());
+            menu.on_key_hprio(
+                OnKey::only(Keycode::Z).control().shift().pressed(), 
+                |ui, this, _| InputBoxState::handle(ui, this, |s, _| s.redo())
             );
             menu.on_key_hprio(
                 OnKey::only(Keycode::Enter).pressed(), 
                 |ui, this, _| {
+                    if this.unique.invalid { return Signal::Refresh }
                     ui.deselect(this);
-                    Signal::Refresh
+                    InputBoxState::fire(ui, this, |s| &mut s.on_submit)
                 }
-            )
+            );
+            // otherwise Escape is left for whatever's around us, ex. a dialog
+            if self.on_cancel.is_some() {
+                menu.on_key_hprio(
+                    OnKey::only(Keycode::Escape).pressed(), 
+                    |ui, this, _| InputBoxState::fire(ui, this, |s| &mut s.on_cancel)
+                );
+            }
         }
 
         let click_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
             match click {
                 MouseEvent::Click(MouseButton::Left, point, _) => {
                     ui.select(this);
-                    this.unique.set_cursor(point.x as usize);
+                    let x = point.x.max(0) as usize + this.unique.scroll.get();
+                    let now = Instant::now();
+                    let double = match this.unique.last_click {
+                        Some((at, last_x)) => last_x == x && now.duration_since(at) <= DOUBLE_CLICK_TIME,
+                        None => false,
+                    };
+                    if double {
+                        this.unique.select_word_at(x);
+                        this.unique.last_click = None;
+                    } else {
+                        let ix = grapheme_at_column(&this.unique.text, x).start;
+                        this.unique.move_to(ix, false);
+                        this.unique.last_click = Some((now, x));
+                    }
                     return Signal::Refresh
                 },
                 MouseEvent::Click(_, _, _) => {}
@@ -78,9 +149,10 @@ impl Widgetlike for InputBoxState {
                     ..
                 } => {
                     if start_point.x < 0 { return Signal::Continue; } // should be impossible
-                    let now_x = now_point.x.max(0) as usize;
+                    let scroll = this.unique.scroll.get();
+                    let now_x = (now_point.x + scroll as isize).max(0) as usize;
 
-                    this.unique.highlight(start_point.x as usize, now_x);
+                    this.unique.highlight(start_point.x as usize + scroll, now_x);
                     return Signal::Refresh
                 },
                 MouseEvent::Drag {..} => {}
@@ -91,19 +163,37 @@ impl Widgetlike for InputBoxState {
         });
 
         let theme = menu.ui.theme().input_box;
-        brush.fill(FSem::new().color(if selected { theme.selected } else { theme.deselected }));
+        let color = if self.invalid { theme.invalid } else if selected { theme.selected } else { theme.deselected };
+        brush.fill(FSem::new().color(color));
         brush.bevel_w95(theme.bevel);
-        brush.putfs(&self.text);  // TODO: Don't wrap?
+
+        // scroll just enough to keep the caret on screen, with a cell to spare for it at the end
+        let width = brush.rect().width().max(1) as usize;
+        let text_w = text_width(&self.text);
+        let caret_x = column_of(&self.text, self.caret);
+        let mut scroll = self.scroll.get().min((text_w + 1).saturating_sub(width));
+        if caret_x < scroll { scroll = caret_x }
+        if caret_x >= scroll + width { scroll = caret_x + 1 - width }
+        self.scroll.replace(scroll);
+
+        // each cluster goes in its own cells, so what's drawn lines up with the caret and the mouse
+        let mut x = 0;
+        for g in self.text.graphemes(true) {
+            if x >= scroll + width { break }
+            let w = cluster_width(g);
+            if x >= scroll { brush.region(rect((x - scroll) as isize, 0, w as isize, 2)).putfs(g); }
+            x += w;
+        }
 
         // make clickable
         brush.interactor(click_interactor, theme.preclick).fill(FSem::new());
 
         // draw cursor
         if selected {
-            let cursor_region = brush.region(rect(
-                self.cursor_l as isize, 0, 
-                (self.cursor_r as isize - self.cursor_l as isize + 1).max(1), 2
-            ));
+            let sel = self.selection();
+            let l = column_of(&self.text, sel.start) as isize - scroll as isize;
+            let r = column_of(&self.text, sel.end) as isize - scroll as isize;
+            let cursor_region = brush.region(rect(l, 0, (r - l).max(1), 2));
             cursor_region.interactor(click_interactor, theme.cursor).fill(FSem::new().color(theme.cursor));
         }
     }
@@ -118,8 +208,9 @@ impl Widgetlike for InputBoxState {
             preferred_w = 80;  // assume the text is quite long!
         }
 
-        if self.text.len() as isize > preferred_w {
-            preferred_w = self.text.len() as isize;
+        let text_w = text_width(&self.text) as isize;
+        if text_w > preferred_w {
+            preferred_w = text_w;
         }
 
         InternalWidgetDimensions { 
@@ -135,95 +226,311 @@ impl Widgetlike for InputBoxState {
     fn clear_layout_cache(&self, _: &UI) { }
 
     fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+
+    fn default_context_menu(widget: &InputBox) -> Option<PopupMenu> {
+        let (has_selection, has_text, can_undo, can_redo) = {
+            let w = widget.borrow();
+            let h = &w.unique.history;
+            (w.unique.caret != w.unique.anchor, !w.unique.text.is_empty(), h.can_undo(), h.can_redo())
+        };
+
+        Some(PopupMenu::new().setup(|p| {
+            p.add_item(edit_item(widget, "Undo", Keycode::U, Keycode::Z, |s, _| s.undo()).with_enabled(can_undo));
+            p.add_item(edit_item(widget, "Redo", Keycode::R, Keycode::Y, |s, _| s.redo()).with_enabled(can_redo));
+            p.add_item(MenuItem::separator());
+            p.add_item(edit_item(widget, "Cut", Keycode::T, Keycode::X, InputBoxState::cut).with_enabled(has_selection));
+            p.add_item(edit_item(widget, "Copy", Keycode::C, Keycode::C, InputBoxState::copy).with_enabled(has_selection));
+            p.add_item(edit_item(widget, "Paste", Keycode::P, Keycode::V, InputBoxState::paste));
+            p.add_item(MenuItem::separator());
+            p.add_item(edit_item(widget, "Select All", Keycode::A, Keycode::A, |s, _| s.select_all()).with_enabled(has_text));
+        }))
+    }
 }
 
 impl InputBoxState {
-    fn type_character(&mut self, character: char) {
-        if self.cursor_l != self.cursor_r {
-            self.text.drain(self.cursor_l..self.cursor_r + 1);
-            self.cursor_r = self.cursor_l;
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    // Doesn't run on_change, which is for edits made by the user.
+    // Also forgets the undo history, since the old steps were for some other text
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.set_cursor(self.text.len());
+        self.history.clear();
+        self.revalidate();
+    }
+
+    // False if the validator called the text Invalid
+    pub fn is_valid(&self) -> bool {
+        !self.invalid
+    }
+
+    pub fn set_validator(&mut self, validator: impl 'static+Fn(&str) -> Validation) {
+        self.validator = Some(Box::new(validator));
+        self.revalidate();
+    }
+
+    pub fn set_on_change(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<InputBoxState>) -> Signal) {
+        self.on_change = Some(Box::new(cmd))
+    }
+
+    // Enter, if the text is valid
+    pub fn set_on_submit(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<InputBoxState>) -> Signal) {
+        self.on_submit = Some(Box::new(cmd))
+    }
+
+    // Escape
+    pub fn set_on_cancel(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<InputBoxState>) -> Signal) {
+        self.on_cancel = Some(Box::new(cmd))
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(carets) = self.history.undo(&mut self.text) { 
+            self.restore(carets);
+            self.revision += 1;
+            self.revalidate();
         }
+    }
 
-        if let Some(mx) = self.max_width {
-            if self.text.len() >= mx {
-                return;
-            }
+    pub fn redo(&mut self) {
+        if let Some(carets) = self.history.redo(&mut self.text) { 
+            self.restore(carets);
+            self.revision += 1;
+            self.revalidate();
         }
+    }
 
-        if self.cursor_l < self.text.len() {
-            self.text.insert(self.cursor_l, character)
-        } else {
-            self.text.push(character)
+    pub fn can_undo(&self) -> bool { self.history.can_undo() }
+    pub fn can_redo(&self) -> bool { self.history.can_redo() }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear()
+    }
+
+    // Ends the current undo step, so the next thing typed is undone separately
+    pub fn snapshot_history(&mut self) {
+        self.history.seal()
+    }
+
+    fn restore(&mut self, (caret, anchor): (usize, usize)) {
+        self.caret = caret;
+        self.anchor = anchor;
+        self.cursor_fixup()
+    }
+
+    // Runs an edit, recording an undo step if it changed anything and the validator didn't reject it
+    fn edit(&mut self, kind: EditKind, f: impl FnOnce(&mut InputBoxState)) {
+        // one line of text, so copying it whole is cheap: undo only keeps the part that changed
+        let before = self.text.clone();
+        let carets = (self.caret, self.anchor);
+        f(self);
+        if self.text == before { return }
+
+        let validation = self.validate();
+        if validation == Validation::Reject { 
+            self.text = before;
+            return self.restore(carets)
         }
-        self.cursor_l += 1;
-        self.cursor_r += 1;
+        self.invalid = validation == Validation::Invalid;
+
+        self.history.record(0, before, &self.text, (carets, (self.caret, self.anchor)), kind);
+        self.revision += 1;
     }
 
-    fn backspace(&mut self) {
-        if self.cursor_l != self.cursor_r {
-            self.text.drain(self.cursor_l..self.cursor_r + 1);
-            self.cursor_r = self.cursor_l;
-            return
+    fn validate(&self) -> Validation {
+        match &self.validator {
+            Some(v) => v(&self.text),
+            None => Validation::Valid,
         }
+    }
+
+    // For text that didn't come from an edit, so there's nothing to reject
+    fn revalidate(&mut self) {
+        self.invalid = self.validate() != Validation::Valid;
+    }
 
-        if self.cursor_l == 0 {
-            return;
+    // Runs something that may edit the text on behalf of the user, then tells on_change if it did
+    fn handle(ui: UI, this: &mut WidgetCommon<Self>, f: impl FnOnce(&mut InputBoxState, &UI)) -> Signal {
+        let revision = this.unique.revision;
+        f(&mut this.unique, &ui);
+        if this.unique.revision == revision { return Signal::Refresh }
+        InputBoxState::fire(ui, this, |s| &mut s.on_change)
+    }
+
+    fn fire(
+        ui: UI, this: &mut WidgetCommon<Self>, 
+        slot: fn(&mut InputBoxState) -> &mut Option<Box<dyn FnMut(UI, &mut WidgetCommon<InputBoxState>) -> Signal>>,
+    ) -> Signal {
+        let callback = slot(&mut this.unique).take();
+        if let Some(mut c) = callback {
+            let result = c(ui, this);
+            // unless the callback installed a new one
+            let slot = slot(&mut this.unique);
+            if slot.is_none() { *slot = Some(c) }
+            return result
         }
-        else {
-            self.text.remove(self.cursor_l - 1);
-            self.cursor_l -= 1;
-            self.cursor_r = self.cursor_l;
+        Signal::Refresh
+    }
+
+    fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    fn cut(&mut self, ui: &UI) {
+        self.copy(ui);
+        self.edit(EditKind::Other, |s| { s.delete_selection(); });
+    }
+
+    fn copy(&mut self, ui: &UI) {
+        if self.caret == self.anchor { return }
+        ui.set_clipboard_text(self.selected_text());
+    }
+
+    fn paste(&mut self, ui: &UI) {
+        let text = ui.clipboard_text();
+        self.edit(EditKind::Other, |s| { for c in text.chars() { s.type_character(c) } });
+    }
+
+    fn select_all(&mut self) {
+        self.anchor = 0;
+        self.caret = self.text.len();
+    }
+
+    fn type_character(&mut self, character: char) {
+        // tab and friends are for focus traversal, not for typing
+        if character.is_control() { return; }
+
+        self.delete_selection();
+
+        self.text.insert(self.caret, character);
+        if let Some(mx) = self.max_width {
+            // measured after the fact, since a combining mark may not widen the text at all
+            if text_width(&self.text) > mx {
+                self.text.remove(self.caret);
+                return;
+            }
         }
+        // a combining mark joins the cluster before it, so this still lands on a boundary
+        self.set_cursor(self.caret + character.len_utf8());
+    }
+
+    fn backspace(&mut self) {
+        if self.delete_selection() { return }
+        let prev = prev_boundary(&self.text, self.caret);
+        self.delete_range(prev..self.caret);
     }
 
     fn delete(&mut self) {
-        if self.cursor_l != self.cursor_r {
-            self.text.drain(self.cursor_l..self.cursor_r + 1);
-            self.cursor_r = self.cursor_l;
-            return
-        }
+        if self.delete_selection() { return }
+        let next = next_boundary(&self.text, self.caret);
+        self.delete_range(self.caret..next);
+    }
 
-        if self.cursor_l >= self.text.len() {
-            return;
-        }
-        else {
-            self.text.remove(self.cursor_l);
-        }
+    fn backspace_word(&mut self) {
+        if self.delete_selection() { return }
+        let prev = prev_word(&self.text, self.caret);
+        self.delete_range(prev..self.caret);
+    }
+
+    fn delete_word(&mut self) {
+        if self.delete_selection() { return }
+        let next = next_word(&self.text, self.caret);
+        self.delete_range(self.caret..next);
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        if self.caret == self.anchor { return false }
+        self.delete_range(self.selection());
+        true
+    }
+
+    fn delete_range(&mut self, range: Range<usize>) {
+        let start = range.start;
+        self.text.drain(range);
+        self.set_cursor(start);
     }
 
     fn set_cursor(&mut self, value: usize) {
-        self.cursor_l = value;
-        self.cursor_r = value;
-        self.cursor_fixup()
+        self.move_to(value, false)
     }
 
-    fn move_cursor(&mut self, amount: isize) {
-        if amount > 0 {
-            // move right, so start from right cursor
-            self.cursor_l = self.cursor_r;
-        }
+    // With `extend`, the anchor stays put and the selection grows or shrinks
+    fn move_to(&mut self, value: usize, extend: bool) {
+        self.caret = value;
+        if !extend { self.anchor = value; }
+        self.cursor_fixup()
+    }
 
-        let mut cursor_2 = self.cursor_l as isize + amount;
-        if cursor_2 < 0 { cursor_2 = 0; }
-        if cursor_2 > self.text.len() as isize { cursor_2 = self.text.len() as isize; }
-        self.cursor_l = cursor_2 as usize;
-        self.cursor_r = self.cursor_l;
+    fn move_left(&mut self, extend: bool) {
+        // collapse the selection toward the side we're moving in
+        if !extend && self.caret != self.anchor { return self.set_cursor(self.selection().start) }
+        self.move_to(prev_boundary(&self.text, self.caret), extend)
     }
 
-    fn highlight(&mut self, i0: usize, i1: usize) {
-        let l = i0.min(i1);
-        let r = i0.max(i1);
+    fn move_right(&mut self, extend: bool) {
+        if !extend && self.caret != self.anchor { return self.set_cursor(self.selection().end) }
+        self.move_to(next_boundary(&self.text, self.caret), extend)
+    }
 
-        self.cursor_l = l;
-        self.cursor_r = r;
+    // Selects every grapheme touched by columns x0 through x1, leaving the caret at the x1 end
+    fn highlight(&mut self, x0: usize, x1: usize) {
+        let g0 = grapheme_at_column(&self.text, x0);
+        let g1 = grapheme_at_column(&self.text, x1);
+        if x1 >= x0 {
+            self.anchor = g0.start;
+            self.caret = g1.end;
+        } else {
+            self.anchor = g0.end;
+            self.caret = g1.start;
+        }
         self.cursor_fixup()
     }
 
-    fn cursor_fixup(&mut self) {
-        if self.cursor_l > self.text.len() { self.cursor_l = self.text.len() }
-        if self.cursor_r > self.text.len() { self.cursor_r = self.text.len() }
-        if self.cursor_r != self.cursor_l && self.cursor_r == self.text.len() {
-            self.cursor_r = self.text.len() - 1
+    fn select_word_at(&mut self, x: usize) {
+        let ix = grapheme_at_column(&self.text, x).start;
+        let word = self.text.split_word_bound_indices()
+            .map(|(i, w)| i..i + w.len())
+            .find(|r| r.contains(&ix));
+        if let Some(r) = word {
+            self.anchor = r.start;
+            self.caret = r.end;
+            self.cursor_fixup()
+        } else {
+            self.set_cursor(self.text.len())
         }
     }
-}
\ No newline at end of file
+
+    fn cursor_fixup(&mut self) {
+        self.caret = snap_to_boundary(&self.text, self.caret);
+        self.anchor = snap_to_boundary(&self.text, self.anchor);
+    }
+}
+
+fn edit_item(widget: &InputBox, text: &str, mnemonic: Keycode, shortcut: Keycode, f: fn(&mut InputBoxState, &UI)) -> MenuItem {
+    let w = widget.share();
+    MenuItem::action(text, move |ui| {
+        InputBoxState::handle(ui, &mut w.borrow_mut(), f)
+    }).with_mnemonic(mnemonic).with_accelerator(Accelerator::control(shortcut))
+}
+
+// Binds the key alone (or with Ctrl) to move the caret, and with Shift added to extend the selection
+fn bind_motion<'frame>(menu: &WidgetMenu<'frame, InputBoxState>, key: Keycode, control: bool, motion: fn(&mut InputBoxState, bool)) {
+    let recognizer = |shift: bool| {
+        let mut k = OnKey::only(key);
+        if control { k = k.control() }
+        if shift { k = k.shift() }
+        k.pressed()
+    };
+    menu.on_key_hprio(recognizer(false), move |_, this, _| { motion(&mut this.unique, false); Signal::Refresh });
+    menu.on_key_hprio(recognizer(true), move |_, this, _| { motion(&mut this.unique, true); Signal::Refresh });
+}
+
+// The same edits from the keyboard, on Ctrl+key
+fn bind_edit<'frame>(menu: &WidgetMenu<'frame, InputBoxState>, key: Keycode, f: fn(&mut InputBoxState, &UI)) {
+    menu.on_key_hprio(OnKey::only(key).control().pressed(), move |ui, this, _| InputBoxState::handle(ui, this, f));
+}
@@ -1,5 +1,16 @@
 mod button;
+mod checkbox;
+mod combo_box;
+mod graphemes;
+mod history;
 mod input_box;
+mod radio_group;
+mod text_area;
 
 pub use button::{Button, ButtonState};
-pub use input_box::{InputBox, InputBoxState};
\ No newline at end of file
+pub use checkbox::{Checkbox, CheckboxState, CheckState};
+pub use combo_box::{ComboBox, ComboBoxState};
+pub(crate) use graphemes::text_width;
+pub use input_box::{InputBox, InputBoxState, Validation};
+pub use radio_group::{RadioGroup, RadioGroupState, RadioOption};
+pub use text_area::{TextArea, TextAreaState, TextPosition};
\ No newline at end of file
@@ -0,0 +1,132 @@
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetCommon, WidgetMenu, Widgetlike, widget::LayoutHacks};
+
+use super::checkbox::{draw_toggle, estimate_toggle};
+
+pub type RadioGroup = Widget<RadioGroupState>;
+
+pub struct RadioOption {
+    pub hotkey: Option<Keycode>,
+    pub text: String,
+}
+
+pub struct RadioGroupState {
+    pub options: Vec<RadioOption>,
+    pub selected: Option<usize>,
+    pub on_change: Option<Box<dyn FnMut(UI, &mut WidgetCommon<RadioGroupState>, usize) -> Signal>>,
+
+    pub layout_hacks: LayoutHacks,
+}
+
+impl RadioGroupState {
+    pub fn add_option(&mut self, text: impl Into<String>, hotkey: Option<Keycode>) {
+        self.options.push(RadioOption { hotkey, text: text.into() })
+    }
+
+    pub fn set_on_change(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<RadioGroupState>, usize) -> Signal) {
+        self.on_change = Some(Box::new(cmd))
+    }
+}
+
+impl Widgetlike for RadioGroupState {
+    fn create() -> Self {
+        Self {
+            options: vec![],
+            selected: None,
+            on_change: None,
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        menu.register_focus();
+
+        if selected {
+            menu.on_key_hprio(OnKey::only(Keycode::Up).pressed(), move |ui, this, _| {
+                match this.unique.selected {
+                    Some(0) | None => Signal::Continue,
+                    Some(i) => RadioGroupState::choose(ui, this, i - 1),
+                }
+            });
+            menu.on_key_hprio(OnKey::only(Keycode::Down).pressed(), move |ui, this, _| {
+                let next = this.unique.selected.map(|i| i + 1).unwrap_or(0);
+                if next >= this.unique.options.len() { return Signal::Continue }
+                RadioGroupState::choose(ui, this, next)
+            });
+        }
+
+        let theme = menu.ui.theme().radio_group;
+        let width = brush.rect().width();
+        let mut y = 0;
+        for (i, option) in self.options.iter().enumerate() {
+            let click_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => { 
+                        return RadioGroupState::choose(ui, this, i);
+                    },
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+
+            if let Some(hotkey) = option.hotkey {
+                menu.on_key(OnKey::only(hotkey).pressed(), move |ui, this, _| {
+                    RadioGroupState::choose(ui, this, i)
+                });
+            }
+
+            let is_chosen = self.selected == Some(i);
+            let mark = if is_chosen { Some(0x07u16) } else { None };  // CP437 bullet
+            // as tall as the text wraps to, like estimate_dimensions says
+            let height = estimate_toggle(&option.text, width).preferred.height;
+            draw_toggle(
+                brush.region(rect(0, y, width, height)),
+                click_interactor, theme,
+                // with nothing chosen, show focus on the first option
+                selected && (is_chosen || (self.selected.is_none() && i == 0)),
+                mark, &option.text,
+            );
+            y += height;
+        }
+    }
+
+    fn estimate_dimensions(&self, _ui: &UI, width: isize) -> InternalWidgetDimensions {
+        let mut dims = InternalWidgetDimensions::zero();
+        dims.align_size_to = size2(1, 2);
+        for option in self.options.iter() {
+            let d = estimate_toggle(&option.text, width);
+            dims.min.width = dims.min.width.max(d.min.width);
+            dims.preferred.width = dims.preferred.width.max(d.preferred.width);
+            dims.min.height += d.min.height;
+            dims.preferred.height += d.preferred.height;
+        }
+        dims
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl RadioGroupState {
+    fn choose(ui: UI, this: &mut WidgetCommon<Self>, i: usize) -> Signal {
+        ui.select(this);
+        if this.unique.selected == Some(i) { return Signal::Refresh }
+        this.unique.selected = Some(i);
+
+        let on_change = this.unique.on_change.take();
+        if let Some(mut c) = on_change {
+            let result = c(ui, this, i);
+            this.unique.on_change.replace(c);
+            return result
+        }
+        Signal::Refresh
+    }
+}
@@ -0,0 +1,567 @@
+use std::cell::Cell;
+
+use chiropterm::*;
+use euclid::{rect, size2};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{InternalWidgetDimensions, UI, Widget, Widgetlike, layout::{Scrolls, draw_scrollbar, fix_offset}, widget::{LayoutHacks, WidgetMenu}};
+
+use super::{graphemes::{cluster_width, column_of, grapheme_at_column, next_boundary, prev_boundary, text_width}, history::{EditKind, History}};
+
+pub type TextArea = Widget<TextAreaState>;
+
+// Columns are byte offsets into the line, and always sit between grapheme clusters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+pub struct TextAreaState {
+    lines: Vec<String>,
+    caret: TextPosition,
+    anchor: Option<TextPosition>,
+    desired_x: Option<usize>,  // remembered by up/down so short lines don't lose the column. In cells, not bytes
+    history: History<Vec<String>, Carets>,
+
+    offset: Cell<f64>,
+    scroll_x: Cell<usize>,  // first visible cell, when not wrapping
+    follow_caret: Cell<bool>,
+    viewport: Cell<CellSize>,  // size of the text region at the last draw, excluding the scrollbar
+
+    pub wrap: bool,
+    pub visible_lines: usize,
+    pub layout_hacks: LayoutHacks,
+}
+
+// What undo puts back besides the text: caret, anchor
+type Carets = (TextPosition, Option<TextPosition>);
+
+// One row on the screen: a (possibly wrapped) slice of a line, from byte `start` to byte `end`
+#[derive(Clone, Copy)]
+struct VisualRow {
+    line: usize,
+    start: usize,
+    end: usize,
+    last_of_line: bool,
+}
+
+impl Scrolls for TextAreaState {
+    fn scroll_offset(&self) -> &Cell<f64> { &self.offset }
+}
+
+impl Widgetlike for TextAreaState {
+    fn create() -> Self {
+        Self {
+            lines: vec![String::new()],
+            caret: TextPosition { line: 0, column: 0 },
+            anchor: None,
+            desired_x: None,
+            history: History::new(),
+
+            offset: Cell::new(0.0),
+            scroll_x: Cell::new(0),
+            follow_caret: Cell::new(false),
+            viewport: Cell::new(size2(0, 0)),
+
+            wrap: true,
+            visible_lines: 8,
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, TextAreaState>) {
+        menu.register_focus();
+
+        if selected {
+            menu.on_text_hprio(|_, this, character| { 
+                this.unique.edit(EditKind::Typing, |s| s.type_character(character)); 
+                Signal::Refresh 
+            });
+
+            bind_motion(&menu, Keycode::Left, TextAreaState::move_left);
+            bind_motion(&menu, Keycode::Right, TextAreaState::move_right);
+            bind_motion(&menu, Keycode::Up, |this, extend| this.move_rows(-1, extend));
+            bind_motion(&menu, Keycode::Down, |this, extend| this.move_rows(1, extend));
+            bind_motion(&menu, Keycode::PageUp, |this, extend| this.move_rows(-this.page_rows(), extend));
+            bind_motion(&menu, Keycode::PageDown, |this, extend| this.move_rows(this.page_rows(), extend));
+            bind_motion(&menu, Keycode::Home, |this, extend| this.move_to(TextPosition { line: this.caret.line, column: 0 }, extend));
+            bind_motion(&menu, Keycode::End, |this, extend| this.move_to(TextPosition { line: this.caret.line, column: this.line_len(this.caret.line) }, extend));
+
+            bind_edit(&menu, Keycode::X, TextAreaState::cut);
+            bind_edit(&menu, Keycode::C, TextAreaState::copy);
+            bind_edit(&menu, Keycode::V, TextAreaState::paste);
+            bind_edit(&menu, Keycode::A, |this, _| this.select_all());
+            bind_edit(&menu, Keycode::Z, |this, _| this.undo());
+            bind_edit(&menu, Keycode::Y, |this, _| this.redo());
+            menu.on_key_hprio(OnKey::only(Keycode::Z).control().shift().pressed(), |_, this, _| { this.unique.redo(); Signal::Refresh });
+
+            menu.on_key_hprio( 
+                OnKey::only(Keycode::Enter).pressed(),
+                |_, this, _| {this.unique.edit(EditKind::Other, TextAreaState::newline); Signal::Refresh }
+            );
+            menu.on_key_hprio( 
+                OnKey::only(Keycode::Backspace).pressed(),
+                |_, this, _| {this.unique.edit(EditKind::Deleting, TextAreaState::backspace); Signal::Refresh }
+            );
+            menu.on_key_hprio( 
+                OnKey::only(Keycode::Delete).pressed(),
+                |_, this, _| {this.unique.edit(EditKind::Deleting, TextAreaState::delete); Signal::Refresh }
+            );
+        }
+
+        let theme = menu.ui.theme().input_box;
+        brush.fill(FSem::new().color(if selected { theme.selected } else { theme.deselected }));
+        brush.bevel_w95(theme.bevel);
+
+        let height = brush.rect().height();
+        let mut text_w = brush.rect().width();
+        let mut rows = self.rows(text_w);
+        let mut inner_height = rows.len() as isize * 2;
+        if inner_height > height {
+            // make room for the scrollbar
+            text_w -= 2;
+            rows = self.rows(text_w);
+            inner_height = rows.len() as isize * 2;
+        }
+        self.viewport.replace(size2(text_w, height));
+
+        if self.follow_caret.replace(false) {
+            self.scroll_to_caret(&rows, text_w, height);
+        }
+        let offset_to_use = fix_offset(&self.offset, inner_height, height);
+        let scroll_x = if self.wrap { 0 } else { self.scroll_x.get() };
+
+        if inner_height > height {
+            draw_scrollbar(brush.clone(), &menu, inner_height, offset_to_use);
+        }
+
+        let click_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+            match click {
+                MouseEvent::Click(MouseButton::Left, point, _) => {
+                    ui.select(this);
+                    let pos = this.unique.position_at(point);
+                    this.unique.move_to(pos, false);
+                    return Signal::Refresh
+                },
+                MouseEvent::Click(_, _, _) => {}
+                MouseEvent::Up(_, _, _) => {}
+                MouseEvent::Drag {
+                    mouse_button: MouseButton::Left,
+                    start_point, now_point, 
+                    ..
+                } => {
+                    let start = this.unique.position_at(start_point);
+                    let now = this.unique.position_at(now_point);
+                    this.unique.anchor = Some(start);
+                    this.unique.move_to(now, true);
+                    return Signal::Refresh
+                },
+                MouseEvent::Drag {..} => {}
+                MouseEvent::Scroll(_, _, _) => {}
+                MouseEvent::Wiggle {..} => {}
+            };
+            Signal::Continue
+        });
+
+        let text_brush = brush.region(rect(0, 0, text_w, height));
+        text_brush.interactor(click_interactor, theme.preclick).fill(FSem::new());
+
+        let selection = self.selection();
+        let first_row = (offset_to_use / 2) as usize;
+        for (i, row) in rows.iter().enumerate().skip(first_row).take(((height + 1) / 2) as usize) {
+            let y = (i - first_row) as isize * 2;
+            let row_brush = text_brush.region(rect(0, y, text_w, 2));
+
+            // each cluster goes in its own cells, so what's drawn lines up with the caret and the mouse
+            let text = &self.lines[row.line][row.start..row.end];
+            let mut x = 0;
+            for g in text.graphemes(true) {
+                if x >= scroll_x + text_w as usize { break }
+                let w = cluster_width(g);
+                if x >= scroll_x { row_brush.region(rect((x - scroll_x) as isize, 0, w as isize, 2)).putfs(g); }
+                x += w;
+            }
+
+            let to_screen = |column: usize| self.column_on_row(row, column) as isize - scroll_x as isize;
+            if let Some((start, end)) = selection {
+                let row_start = TextPosition { line: row.line, column: row.start };
+                let row_end = TextPosition { line: row.line, column: row.end };
+                if start <= row_end && end >= row_start {
+                    let l = to_screen(if start > row_start { start.column } else { row.start });
+                    let mut r = to_screen(if end < row_end { end.column } else { row.end });
+                    // show the selected line break too
+                    if end > row_end && row.last_of_line { r += 1; }
+                    if r > l {
+                        row_brush.region(rect(l, 0, r - l, 2)).fill(FSem::new().color(theme.cursor));
+                    }
+                }
+            } else if selected && self.caret_on_row(row) {
+                row_brush.region(rect(to_screen(self.caret.column), 0, 1, 2)).fill(FSem::new().color(theme.cursor));
+            }
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        InternalWidgetDimensions { 
+            min: size2(8, 4),
+            preferred: size2(80, self.visible_lines as isize * 2),
+            max: None,
+            align_size_to: size2(1, 2),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl TextAreaState {
+    pub fn get_text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn set_text(&mut self, text: impl AsRef<str>) {
+        self.lines = text.as_ref().split('\n').map(|l| l.to_owned()).collect();
+        self.caret = TextPosition { line: 0, column: 0 };
+        self.anchor = None;
+        self.desired_x = None;
+        self.offset.replace(0.0);
+        self.scroll_x.replace(0);
+        self.history.clear();
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(carets) = self.history.undo(&mut self.lines) { self.restore(carets) }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(carets) = self.history.redo(&mut self.lines) { self.restore(carets) }
+    }
+
+    pub fn can_undo(&self) -> bool { self.history.can_undo() }
+    pub fn can_redo(&self) -> bool { self.history.can_redo() }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear()
+    }
+
+    // Ends the current undo step, so the next thing typed is undone separately
+    pub fn snapshot_history(&mut self) {
+        self.history.seal()
+    }
+
+    pub fn caret(&self) -> TextPosition {
+        self.caret
+    }
+
+    // Ordered (start, end) of the highlighted text, if any
+    pub fn selection(&self) -> Option<(TextPosition, TextPosition)> {
+        let anchor = self.anchor?;
+        if anchor == self.caret { return None }
+        Some((anchor.min(self.caret), anchor.max(self.caret)))
+    }
+
+    pub fn select_all(&mut self) {
+        let last = self.lines.len() - 1;
+        self.anchor = Some(TextPosition { line: 0, column: 0 });
+        self.caret = TextPosition { line: last, column: self.line_len(last) };
+        self.follow_caret.replace(true);
+    }
+}
+
+impl TextAreaState {
+    fn line_len(&self, line: usize) -> usize {
+        self.lines[line].len()
+    }
+
+    fn selected_text(&self) -> String {
+        let (start, end) = if let Some(s) = self.selection() { s } else { return String::new() };
+        if start.line == end.line {
+            return self.lines[start.line][start.column..end.column].to_owned()
+        }
+
+        let mut text = self.lines[start.line][start.column..].to_owned();
+        for line in &self.lines[start.line + 1..end.line] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.push_str(&self.lines[end.line][..end.column]);
+        text
+    }
+
+    fn cut(&mut self, ui: &UI) {
+        self.copy(ui);
+        self.edit(EditKind::Other, |s| if s.delete_selection() { s.edited() });
+    }
+
+    fn copy(&mut self, ui: &UI) {
+        if self.selection().is_none() { return }
+        ui.set_clipboard_text(self.selected_text());
+    }
+
+    fn paste(&mut self, ui: &UI) {
+        let text = ui.clipboard_text();
+        self.edit(EditKind::Other, |s| {
+            for c in text.chars() {
+                match c {
+                    '\n' => s.newline(),
+                    '\r' => {}
+                    _ => s.type_character(c),
+                }
+            }
+        });
+    }
+
+    fn restore(&mut self, (caret, anchor): Carets) {
+        self.caret = caret;
+        self.anchor = anchor;
+        self.edited();
+    }
+
+    // Runs an edit, recording an undo step if it changed anything.
+    // An edit may reach one line past either end of the caret or selection (backspace and delete join lines), but no further,
+    // and it may put any number of lines in their place (ex. a paste). The `untouched` lines after that are the same afterwards,
+    // so counting them from the end finds them again: only what lies between `first` and them is copied for undo
+    fn edit(&mut self, kind: EditKind, f: impl FnOnce(&mut TextAreaState)) {
+        let carets = (self.caret, self.anchor);
+        let (start, end) = match self.anchor {
+            Some(a) => (a.line.min(self.caret.line), a.line.max(self.caret.line)),
+            None => (self.caret.line, self.caret.line),
+        };
+        let first = start.saturating_sub(1);
+        let last = (end + 1).min(self.lines.len() - 1);
+        let untouched = self.lines.len() - 1 - last;
+        let before = self.lines[first..=last].to_vec();
+
+        f(self);
+
+        let after = self.lines[first..self.lines.len() - untouched].to_vec();
+        self.history.record(first, before, &after, (carets, (self.caret, self.anchor)), kind);
+    }
+
+    fn type_character(&mut self, character: char) {
+        // tab and friends are for focus traversal, not for typing
+        if character.is_control() { return; }
+
+        self.delete_selection();
+        self.lines[self.caret.line].insert(self.caret.column, character);
+        // a combining mark joins the cluster before it, so this still lands on a boundary
+        self.caret.column += character.len_utf8();
+        self.edited();
+    }
+
+    fn newline(&mut self) {
+        self.delete_selection();
+        let rest = self.lines[self.caret.line].split_off(self.caret.column);
+        self.lines.insert(self.caret.line + 1, rest);
+        self.caret = TextPosition { line: self.caret.line + 1, column: 0 };
+        self.edited();
+    }
+
+    fn backspace(&mut self) {
+        if self.delete_selection() { return self.edited() }
+
+        if self.caret.column > 0 {
+            let line = &mut self.lines[self.caret.line];
+            let prev = prev_boundary(line, self.caret.column);
+            line.replace_range(prev..self.caret.column, "");
+            self.caret.column = prev;
+        } else if self.caret.line > 0 {
+            let line = self.lines.remove(self.caret.line);
+            self.caret.line -= 1;
+            self.caret.column = self.line_len(self.caret.line);
+            self.lines[self.caret.line].push_str(&line);
+        }
+        self.edited();
+    }
+
+    fn delete(&mut self) {
+        if self.delete_selection() { return self.edited() }
+
+        if self.caret.column < self.line_len(self.caret.line) {
+            let line = &mut self.lines[self.caret.line];
+            let next = next_boundary(line, self.caret.column);
+            line.replace_range(self.caret.column..next, "");
+        } else if self.caret.line + 1 < self.lines.len() {
+            let line = self.lines.remove(self.caret.line + 1);
+            self.lines[self.caret.line].push_str(&line);
+        }
+        self.edited();
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let (start, end) = match self.selection() {
+            Some(s) => s,
+            None => { self.anchor = None; return false }
+        };
+
+        let tail = self.lines[end.line][end.column..].to_owned();
+        self.lines[start.line].truncate(start.column);
+        self.lines[start.line].push_str(&tail);
+        self.lines.drain(start.line + 1..end.line + 1);
+
+        self.caret = start;
+        self.anchor = None;
+        true
+    }
+
+    fn edited(&mut self) {
+        self.desired_x = None;
+        self.follow_caret.replace(true);
+    }
+
+    fn move_to(&mut self, pos: TextPosition, extend: bool) {
+        if extend {
+            if self.anchor.is_none() { self.anchor = Some(self.caret) }
+        } else {
+            self.anchor = None;
+        }
+        self.caret = pos;
+        self.desired_x = None;
+        self.follow_caret.replace(true);
+    }
+
+    fn move_left(&mut self, extend: bool) {
+        if let (Some((start, _)), false) = (self.selection(), extend) {
+            return self.move_to(start, false)
+        }
+
+        let mut pos = self.caret;
+        if pos.column > 0 {
+            pos.column = prev_boundary(&self.lines[pos.line], pos.column);
+        } else if pos.line > 0 {
+            pos.line -= 1;
+            pos.column = self.line_len(pos.line);
+        }
+        self.move_to(pos, extend)
+    }
+
+    fn move_right(&mut self, extend: bool) {
+        if let (Some((_, end)), false) = (self.selection(), extend) {
+            return self.move_to(end, false)
+        }
+
+        let mut pos = self.caret;
+        if pos.column < self.line_len(pos.line) {
+            pos.column = next_boundary(&self.lines[pos.line], pos.column);
+        } else if pos.line + 1 < self.lines.len() {
+            pos.line += 1;
+            pos.column = 0;
+        }
+        self.move_to(pos, extend)
+    }
+
+    fn move_rows(&mut self, amount: isize, extend: bool) {
+        let rows = self.rows(self.viewport.get().width);
+        let current = rows.iter().position(|r| self.caret_on_row(r)).unwrap_or(0);
+        let x = self.desired_x.unwrap_or_else(|| self.column_on_row(&rows[current], self.caret.column));
+
+        let target = (current as isize + amount).max(0).min(rows.len() as isize - 1) as usize;
+        let row = rows[target];
+        let mut column = self.position_on_row(&row, x);
+        // the end of a wrapped row is the start of the next one, so stay inside this one
+        if !row.last_of_line && column == row.end && column > row.start {
+            column = prev_boundary(&self.lines[row.line], column);
+        }
+
+        self.move_to(TextPosition { line: row.line, column }, extend);
+        self.desired_x = Some(x);
+    }
+
+    fn page_rows(&self) -> isize {
+        (self.viewport.get().height / 2 - 1).max(1)
+    }
+
+    fn rows(&self, width: isize) -> Vec<VisualRow> {
+        let mut rows = vec![];
+        for (line, text) in self.lines.iter().enumerate() {
+            if !self.wrap || width <= 0 || text_width(text) <= width as usize {
+                rows.push(VisualRow { line, start: 0, end: text.len(), last_of_line: true });
+                continue;
+            }
+
+            // break before whichever cluster would overflow, but keep at least one on every row
+            let width = width as usize;
+            let mut start = 0;
+            let mut x = 0;
+            for (i, g) in text.grapheme_indices(true) {
+                let w = cluster_width(g);
+                if x + w > width && i > start {
+                    rows.push(VisualRow { line, start, end: i, last_of_line: false });
+                    start = i;
+                    x = 0;
+                }
+                x += w;
+            }
+            rows.push(VisualRow { line, start, end: text.len(), last_of_line: true });
+        }
+        rows
+    }
+
+    // How many cells into the row this column is drawn
+    fn column_on_row(&self, row: &VisualRow, column: usize) -> usize {
+        text_width(&self.lines[row.line][row.start..column])
+    }
+
+    // The column drawn this many cells into the row, or the end of the row if it's shorter
+    fn position_on_row(&self, row: &VisualRow, x: usize) -> usize {
+        row.start + grapheme_at_column(&self.lines[row.line][row.start..row.end], x).start
+    }
+
+    fn caret_on_row(&self, row: &VisualRow) -> bool {
+        row.line == self.caret.line && row.start <= self.caret.column && 
+            (self.caret.column < row.end || (row.last_of_line && self.caret.column == row.end))
+    }
+
+    fn position_at(&self, point: CellPoint) -> TextPosition {
+        let rows = self.rows(self.viewport.get().width);
+        let mut offset = self.offset.get() as isize;
+        offset -= offset % 2;
+
+        let ix = ((point.y + offset) / 2).max(0).min(rows.len() as isize - 1) as usize;
+        let row = rows[ix];
+        let scroll_x = if self.wrap { 0 } else { self.scroll_x.get() };
+        let column = self.position_on_row(&row, scroll_x + point.x.max(0) as usize);
+        TextPosition { line: row.line, column }
+    }
+
+    fn scroll_to_caret(&self, rows: &[VisualRow], text_w: isize, height: isize) {
+        if let Some(r) = rows.iter().position(|r| self.caret_on_row(r)) {
+            let top = r as isize * 2;
+            let bottom = top + 2;
+            let offset = self.offset.get() as isize;
+            if top < offset {
+                self.offset.replace(top as f64);
+            } else if bottom > offset + height {
+                let mut o = bottom - height;
+                o += o % 2;
+                self.offset.replace(o as f64);
+            }
+        }
+
+        if !self.wrap && text_w > 0 {
+            let x = column_of(&self.lines[self.caret.line], self.caret.column);
+            let scroll_x = self.scroll_x.get();
+            if x < scroll_x {
+                self.scroll_x.replace(x);
+            } else if x >= scroll_x + text_w as usize {
+                self.scroll_x.replace(x + 1 - text_w as usize);
+            }
+        }
+    }
+}
+
+// Binds a key to a caret motion, and shift+key to the same motion extending the selection
+fn bind_motion<'frame>(menu: &WidgetMenu<'frame, TextAreaState>, key: Keycode, motion: fn(&mut TextAreaState, bool)) {
+    menu.on_key_hprio(OnKey::only(key).pressed(), move |_, this, _| { motion(&mut this.unique, false); Signal::Refresh });
+    menu.on_key_hprio(OnKey::only(key).shift().pressed(), move |_, this, _| { motion(&mut this.unique, true); Signal::Refresh });
+}
+
+// Binds Ctrl+key to an edit that needs the UI, ex. for the clipboard
+fn bind_edit<'frame>(menu: &WidgetMenu<'frame, TextAreaState>, key: Keycode, f: fn(&mut TextAreaState, &UI)) {
+    menu.on_key_hprio(OnKey::only(key).control().pressed(), move |ui, this, _| { f(&mut this.unique, &ui); Signal::Refresh });
+}
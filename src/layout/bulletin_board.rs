@@ -1,16 +1,14 @@
-use chiropterm::{Brush, CellPoint};
+use chiropterm::{Brush, CellPoint, CellRect, CellSize};
 use euclid::{rect, size2};
-use smallvec::SmallVec;
 
 use crate::{InternalWidgetDimensions, UI, Widget, WidgetMenu, Widgetlike, widget::{AnyWidget, LayoutHacks}};
 
-// Smallvec size -- set this to "higher than most users will ever put in one column/row"
-const SM: usize = 32;
+use super::children::Children;
 
 pub type BulletinBoard = Widget<BulletinBoardState>;
 
 pub struct BulletinBoardState {
-    widgets: SmallVec<[(CellPoint, AnyWidget); SM]>,
+    widgets: Children<CellPoint>,
 
     pub layout_hacks: LayoutHacks,
 }
@@ -18,23 +16,15 @@ pub struct BulletinBoardState {
 impl Widgetlike for BulletinBoardState {
     fn create() -> Self {
         BulletinBoardState { 
-            widgets: SmallVec::new(),
+            widgets: Children::new(),
 
             layout_hacks: LayoutHacks::new(),
         }
     }
 
     fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, BulletinBoardState>) {
-        for (offset, w) in &self.widgets {
-            let dims = w.estimate_dimensions(&menu.share().ui, brush.rect().width());
-            let x = offset.x.min(brush.rect().width() - dims.preferred.width).max(0);
-            let y = offset.y.min(brush.rect().height() - dims.preferred.height).max(0);
-
-            w.draw(brush.region(rect(
-                x, y, 
-                dims.preferred.width.min(brush.rect().width() - x),
-                dims.preferred.height.min(brush.rect().height() - y),
-            )), menu.share())
+        for (w, at) in self.placed(&menu.ui, brush.rect().size) {
+            w.draw(brush.region(at), menu.share())
         }
     }
 
@@ -45,7 +35,7 @@ impl Widgetlike for BulletinBoardState {
         let mut max_pref_x = 0;
         let mut max_pref_y = 0;
 
-        for (offset, w) in &self.widgets {
+        for (w, offset) in self.widgets.iter() {
             let dims = w.estimate_dimensions(ui, width - offset.x);
 
             // at minimum, widgets will be edged onto the screen from outside
@@ -67,16 +57,73 @@ impl Widgetlike for BulletinBoardState {
     }
 
     fn clear_layout_cache(&self, ui: &UI) {
-        for (_, w) in self.widgets.iter() {
-            w.clear_layout_cache_if_needed(&ui)
-        }
+        self.widgets.clear_layout_cache(ui);
     }
 
     fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
 }
 
 impl BulletinBoardState {
+    pub fn len(&self) -> usize {
+        self.widgets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.widgets.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=(CellPoint, &AnyWidget)> {
+        self.widgets.iter().map(|(w, at)| (*at, w))
+    }
+
+    pub fn clear(&mut self) {
+        self.widgets.clear()
+    }
+
     pub fn add<X: Into<AnyWidget>>(&mut self, at: CellPoint, w: X) {
-        self.widgets.push((at, w.into()))
+        self.widgets.push(w.into(), at)
+    }
+
+    pub fn insert<X: Into<AnyWidget>>(&mut self, ix: usize, at: CellPoint, w: X) {
+        self.widgets.insert(ix, w.into(), at)
+    }
+
+    pub fn remove(&mut self, ix: usize) -> AnyWidget {
+        self.widgets.remove(ix)
+    }
+
+    // Returns false if the widget isn't a direct child
+    pub fn remove_widget<X: Into<AnyWidget>>(&mut self, w: X) -> bool {
+        self.widgets.remove_widget(&w.into())
+    }
+
+    // `to` is the index the widget ends up at
+    pub fn move_widget(&mut self, from: usize, to: usize) {
+        self.widgets.move_widget(from, to)
+    }
+
+    // Keeps the old widget's position
+    pub fn replace<X: Into<AnyWidget>>(&mut self, ix: usize, w: X) -> AnyWidget {
+        self.widgets.replace(ix, w.into())
+    }
+
+    pub fn set_position(&mut self, ix: usize, at: CellPoint) {
+        self.widgets.set_extra(ix, at)
+    }
+
+    // Where each widget goes on a board this size, back to front: at its preferred size, edged back on if it hangs off.
+    // Only call this when drawing
+    pub(crate) fn placed<'a>(&'a self, ui: &'a UI, size: CellSize) -> impl Iterator<Item=(&'a AnyWidget, CellRect)> {
+        self.widgets.attach(ui);
+        self.widgets.iter().map(move |(w, offset)| {
+            let dims = w.estimate_dimensions(ui, size.width);
+            let x = offset.x.min(size.width - dims.preferred.width).max(0);
+            let y = offset.y.min(size.height - dims.preferred.height).max(0);
+            (w, rect(
+                x, y, 
+                dims.preferred.width.min(size.width - x),
+                dims.preferred.height.min(size.height - y),
+            ))
+        })
     }
 }
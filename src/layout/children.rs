@@ -0,0 +1,99 @@
+use smallvec::SmallVec;
+
+use crate::{UI, ui::LayoutOwner, widget::AnyWidget};
+
+// Smallvec size -- set this to "higher than most users will ever put in one column/row"
+const SM: usize = 32;
+
+// The child list behind Column, Row, Deck and BulletinBoard. X is whatever the container keeps with each child.
+// Every change asks the UI that last drew the container to lay it out again
+pub(crate) struct Children<X> {
+    items: SmallVec<[(AnyWidget, X); SM]>,
+    owner: LayoutOwner,
+}
+
+impl<X> Children<X> {
+    pub(crate) fn new() -> Children<X> {
+        Children { items: SmallVec::new(), owner: LayoutOwner::new() }
+    }
+
+    // Call this when drawing, so changes know which UI to tell
+    pub(crate) fn attach(&self, ui: &UI) {
+        self.owner.attach(ui)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item=&(AnyWidget, X)> {
+        self.items.iter()
+    }
+
+    pub(crate) fn extra(&self, ix: usize) -> &X {
+        &self.items[ix].1
+    }
+
+    pub(crate) fn last(&self) -> Option<&AnyWidget> {
+        self.items.last().map(|(w, _)| w)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.items.clear();
+        self.owner.recompute_layout();
+    }
+
+    pub(crate) fn push(&mut self, w: AnyWidget, extra: X) {
+        self.items.push((w, extra));
+        self.owner.recompute_layout();
+    }
+
+    pub(crate) fn insert(&mut self, ix: usize, w: AnyWidget, extra: X) {
+        self.items.insert(ix, (w, extra));
+        self.owner.recompute_layout();
+    }
+
+    pub(crate) fn remove(&mut self, ix: usize) -> AnyWidget {
+        let (w, _) = self.items.remove(ix);
+        self.owner.recompute_layout();
+        w
+    }
+
+    // Returns false if the widget isn't a direct child
+    pub(crate) fn remove_widget(&mut self, w: &AnyWidget) -> bool {
+        if let Some(ix) = self.items.iter().position(|(x, _)| x.ptr_eq(w)) {
+            self.remove(ix);
+            return true
+        }
+        false
+    }
+
+    // `to` is the index the widget ends up at
+    pub(crate) fn move_widget(&mut self, from: usize, to: usize) {
+        let item = self.items.remove(from);
+        self.items.insert(to, item);
+        self.owner.recompute_layout();
+    }
+
+    // Keeps the old widget's extra
+    pub(crate) fn replace(&mut self, ix: usize, w: AnyWidget) -> AnyWidget {
+        let old = std::mem::replace(&mut self.items[ix].0, w);
+        self.owner.recompute_layout();
+        old
+    }
+
+    pub(crate) fn set_extra(&mut self, ix: usize, extra: X) {
+        self.items[ix].1 = extra;
+        self.owner.recompute_layout();
+    }
+
+    pub(crate) fn clear_layout_cache(&self, ui: &UI) {
+        for (w, _) in self.items.iter() {
+            w.clear_layout_cache_if_needed(ui)
+        }
+    }
+}
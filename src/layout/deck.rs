@@ -1,16 +1,14 @@
 use chiropterm::{Brush};
 use euclid::{rect, size2};
-use smallvec::SmallVec;
 
 use crate::{InternalWidgetDimensions, UI, Widget, WidgetMenu, Widgetlike, widget::{AnyWidget, LayoutHacks}};
 
-// Smallvec size -- set this to "higher than most users will ever put in one column/row"
-const SM: usize = 32;
+use super::children::Children;
 
 pub type Deck = Widget<DeckState>;
 
 pub struct DeckState {
-    widgets: SmallVec<[AnyWidget; SM]>,
+    widgets: Children<()>,
 
     pub layout_hacks: LayoutHacks,
 }
@@ -18,13 +16,14 @@ pub struct DeckState {
 impl Widgetlike for DeckState {
     fn create() -> Self {
         DeckState { 
-            widgets: SmallVec::new(),
+            widgets: Children::new(),
 
             layout_hacks: LayoutHacks::new(),
         }
     }
 
     fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, DeckState>) {
+        self.widgets.attach(&menu.ui);
         let top = if let Some(top) = self.widgets.last() {
             top
         } else { return };
@@ -45,21 +44,15 @@ impl Widgetlike for DeckState {
             menu.share(),
         );
 
-        let mut ix = self.widgets.len() - 1;
-        loop {
+        // the title bars of the cards underneath, nearest the top first. The bottom ones go if there's no room
+        for (w, _) in self.widgets.iter().rev().skip(1) {
             y_tar -= 2;
-            ix -= 1;
+            if y_tar < 0 { break }
 
-            if y_tar < 0 { unreachable!(); }
-
-            self.widgets[ix].draw(
+            w.draw(
                 brush.region(rect(0, y_tar, brush.rect().width(), 2)), 
                 menu.share().with_context(|ctx| ctx.active = false),
             );
-
-            if ix == 0 { 
-                break; 
-            }
         }
     }
 
@@ -67,7 +60,7 @@ impl Widgetlike for DeckState {
         let mut max_min_w = 0;
         let mut max_preferred_w = 0;
 
-        for i in self.widgets.iter() {
+        for (i, _) in self.widgets.iter() {
             let dims = i.estimate_dimensions(ui, width);
             max_min_w = max_min_w.max(dims.min.width);
             max_preferred_w = max_preferred_w.max(dims.preferred.width);
@@ -92,16 +85,53 @@ impl Widgetlike for DeckState {
     }
 
     fn clear_layout_cache(&self, ui: &UI) {
-        for i in self.widgets.iter() {
-            i.clear_layout_cache_if_needed(&ui)
-        }
+        self.widgets.clear_layout_cache(ui);
     }
 
     fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
 }
 
 impl DeckState {
+    pub fn len(&self) -> usize {
+        self.widgets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.widgets.is_empty()
+    }
+
+    // Bottom of the deck first, so the last one is on top
+    pub fn iter(&self) -> impl Iterator<Item=&AnyWidget> {
+        self.widgets.iter().map(|(w, _)| w)
+    }
+
+    pub fn clear(&mut self) {
+        self.widgets.clear()
+    }
+
     pub fn add<X: Into<AnyWidget>>(&mut self, w: X) {
-        self.widgets.push(w.into())
+        self.widgets.push(w.into(), ())
+    }
+
+    pub fn insert<X: Into<AnyWidget>>(&mut self, ix: usize, w: X) {
+        self.widgets.insert(ix, w.into(), ())
+    }
+
+    pub fn remove(&mut self, ix: usize) -> AnyWidget {
+        self.widgets.remove(ix)
+    }
+
+    // Returns false if the widget isn't a direct child
+    pub fn remove_widget<X: Into<AnyWidget>>(&mut self, w: X) -> bool {
+        self.widgets.remove_widget(&w.into())
+    }
+
+    // `to` is the index the widget ends up at
+    pub fn move_widget(&mut self, from: usize, to: usize) {
+        self.widgets.move_widget(from, to)
+    }
+
+    pub fn replace<X: Into<AnyWidget>>(&mut self, ix: usize, w: X) -> AnyWidget {
+        self.widgets.replace(ix, w.into())
     }
 }
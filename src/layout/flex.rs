@@ -0,0 +1,207 @@
+use smallvec::SmallVec;
+
+// Smallvec size -- set this to "higher than most users will ever put in one column/row"
+const SM: usize = 32;
+
+// How a child of a Row or Column reacts when it gets more or less room than it asked for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Flex {
+    // shares of any spare room. None: one share per spacer inside the child
+    pub grow: Option<usize>,
+    // align-steps taken from this child on each pass when short on room. 0: only shrink it when desperate
+    pub shrink: usize,
+    // lower priorities are squeezed down to their minimum before higher ones lose anything
+    pub shrink_priority: isize,
+}
+
+impl Flex {
+    pub fn new() -> Flex {
+        Flex { grow: None, shrink: 1, shrink_priority: 0 }
+    }
+
+    pub fn grow(mut self, grow: usize) -> Flex {
+        self.grow = Some(grow);
+        self
+    }
+
+    pub fn shrink(mut self, shrink: usize) -> Flex {
+        self.shrink = shrink;
+        self
+    }
+
+    pub fn shrink_priority(mut self, shrink_priority: isize) -> Flex {
+        self.shrink_priority = shrink_priority;
+        self
+    }
+}
+
+impl Default for Flex {
+    fn default() -> Flex { Flex::new() }
+}
+
+// One child, measured along the axis being divided up
+pub(crate) struct FlexItem {
+    pub(crate) min: isize,
+    pub(crate) preferred: isize,
+    pub(crate) align: isize,
+    pub(crate) spacer_count: usize,
+    pub(crate) flex: Flex,
+}
+
+// Who gets the spare room if nobody wants it
+#[derive(Clone, Copy)]
+pub(crate) enum Fallback { Last, All, Nobody }
+
+pub(crate) fn distribute(items: &[FlexItem], available: isize, fallback: Fallback) -> SmallVec<[isize; SM]> {
+    let mut practical: SmallVec<[isize; SM]> = items.iter().map(|i| i.preferred).collect();
+    if practical.is_empty() || available < 0 { return practical }
+
+    let practical_sum: isize = practical.iter().sum();
+    if practical_sum < available {
+        grow(items, &mut practical, (available - practical_sum) as usize, fallback);
+    } else if practical_sum > available {
+        shrink(items, &mut practical, practical_sum, available);
+        for p in practical.iter_mut() { *p = (*p).max(0) }
+    }
+    practical
+}
+
+fn grow(items: &[FlexItem], practical: &mut [isize], extra: usize, fallback: Fallback) {
+    let mut weights: SmallVec<[usize; SM]> = items.iter().map(|i| i.flex.grow.unwrap_or(i.spacer_count)).collect();
+    let mut total = weights.iter().fold(0usize, |a, w| a.saturating_add(*w));
+    if total == 0 {
+        // (Basically, we're implicitly adding a spacer.)
+        match fallback {
+            Fallback::Last => { let last = weights.len() - 1; weights[last] = 1; }
+            Fallback::All => { for w in weights.iter_mut() { *w = 1; } }
+            Fallback::Nobody => return,
+        }
+        total = weights.iter().sum();
+    }
+
+    // everything handed out is a whole multiple of the child's alignment, so two-cell-high text still lines up
+    let mut rem = extra as isize;
+    for ((p, w), item) in practical.iter_mut().zip(weights.iter()).zip(items.iter()) {
+        let mut portion = (extra as u128 * *w as u128 / total as u128) as isize;
+        portion -= portion % item.align.max(1);
+        *p += portion;
+        rem -= portion;
+    }
+    // hand out what rounding left over, one step per share, starting at the top.
+    // Anything smaller than every step that could take it goes unused
+    while rem > 0 {
+        let prev_rem = rem;
+        for ((p, w), item) in practical.iter_mut().zip(weights.iter()).zip(items.iter()) {
+            let step = item.align.max(1);
+            let bonus = (rem / step).min(*w as isize) * step;
+            *p += bonus;
+            rem -= bonus;
+        }
+        if rem == prev_rem { break }
+    }
+}
+
+fn shrink(items: &[FlexItem], practical: &mut [isize], mut practical_sum: isize, available: isize) {
+    let mut priorities: SmallVec<[isize; SM]> = items.iter().map(|i| i.flex.shrink_priority).collect();
+    priorities.sort();
+    priorities.dedup();
+
+    for desperate in [false, true].iter().cloned() {
+        for priority in priorities.iter().cloned() {
+            loop {
+                // Steal from everyone in this tier in proportion to their shrink weight, starting at the end
+                let prev_sum = practical_sum;
+                for i in (0..practical.len()).rev() {
+                    let item = &items[i];
+                    if item.flex.shrink_priority != priority { continue }
+
+                    let steps = if desperate { item.flex.shrink.max(1) } else { item.flex.shrink };
+                    for _ in 0..steps {
+                        if !desperate && practical[i] <= item.min { break }
+                        if practical[i] <= 0 { break }
+
+                        practical[i] -= item.align;
+                        practical_sum -= item.align;
+                        if practical_sum <= available { return }
+                    }
+                }
+                if prev_sum == practical_sum { break }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(min: isize, preferred: isize, flex: Flex) -> FlexItem {
+        FlexItem { min, preferred, align: 1, spacer_count: 0, flex }
+    }
+
+    fn plain(preferred: isize) -> FlexItem {
+        item(0, preferred, Flex::new())
+    }
+
+    fn sizes(items: &[FlexItem], available: isize, fallback: Fallback) -> Vec<isize> {
+        distribute(items, available, fallback).to_vec()
+    }
+
+    #[test]
+    fn columns_give_spare_room_to_the_last_child() {
+        assert_eq!(sizes(&[plain(2), plain(2), plain(2)], 10, Fallback::Last), vec![2, 2, 6]);
+    }
+
+    #[test]
+    fn rows_give_spare_room_to_everyone_leftovers_first() {
+        assert_eq!(sizes(&[plain(2), plain(2), plain(2)], 10, Fallback::All), vec![4, 3, 3]);
+    }
+
+    #[test]
+    fn spacers_and_grow_outweigh_the_fallback() {
+        let spacer = FlexItem { spacer_count: 1, ..plain(2) };
+        assert_eq!(sizes(&[spacer, plain(2)], 10, Fallback::Last), vec![8, 2]);
+        assert_eq!(sizes(&[item(0, 2, Flex::new().grow(1)), item(0, 2, Flex::new().grow(2))], 10, Fallback::All), vec![4, 6]);
+    }
+
+    #[test]
+    fn growing_keeps_to_each_childs_alignment() {
+        let text = || FlexItem { align: 2, spacer_count: 1, ..plain(2) };
+        // the odd cell left over is too small for the text, so the next child that wants room gets it
+        assert_eq!(sizes(&[text(), item(0, 2, Flex::new().grow(1))], 10, Fallback::Last), vec![4, 6]);
+        // and if nobody else wants it, it goes unused
+        assert_eq!(sizes(&[text(), plain(2)], 9, Fallback::Last), vec![6, 2]);
+    }
+
+    #[test]
+    fn nobody_can_leave_spare_room_unused() {
+        assert_eq!(sizes(&[plain(2), plain(2)], 10, Fallback::Nobody), vec![2, 2]);
+        let spacer = FlexItem { spacer_count: 1, ..plain(2) };
+        assert_eq!(sizes(&[plain(2), spacer], 10, Fallback::Nobody), vec![2, 8]);
+    }
+
+    #[test]
+    fn an_exact_fit_is_left_alone() {
+        assert_eq!(sizes(&[plain(4), plain(4)], 8, Fallback::Last), vec![4, 4]);
+    }
+
+    #[test]
+    fn shrinking_takes_evenly_starting_at_the_end() {
+        assert_eq!(sizes(&[plain(4), plain(4), plain(4)], 10, Fallback::Last), vec![4, 3, 3]);
+        assert_eq!(sizes(&[plain(4), plain(4), plain(4)], 9, Fallback::Last), vec![3, 3, 3]);
+    }
+
+    #[test]
+    fn lower_shrink_priorities_give_way_first() {
+        let items = [item(1, 4, Flex::new()), item(1, 4, Flex::new().shrink_priority(1))];
+        assert_eq!(sizes(&items, 6, Fallback::Last), vec![2, 4]);
+        assert_eq!(sizes(&items, 3, Fallback::Last), vec![1, 2]);
+    }
+
+    #[test]
+    fn shrink_zero_is_spared_until_desperate() {
+        let items = [item(0, 4, Flex::new().shrink(0)), item(2, 4, Flex::new())];
+        assert_eq!(sizes(&items, 6, Fallback::Last), vec![4, 2]);
+        assert_eq!(sizes(&items, 4, Fallback::Last), vec![3, 1]);
+    }
+}
@@ -0,0 +1,95 @@
+use chiropterm::{Brush, CellRect};
+use euclid::{rect, size2};
+use smallvec::SmallVec;
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetMenu, Widgetlike, widget::{AnyWidget, LayoutHacks}};
+
+// Smallvec size -- set this to "more children than most users will ever put in one flow"
+const SM: usize = 32;
+
+pub type Flow = Widget<FlowState>;
+
+// Like a Row, but children that don't fit wrap onto the next line, the way words in a Label do
+pub struct FlowState {
+    widgets: SmallVec<[AnyWidget; SM]>,
+
+    pub spacing: isize,  // between neighbours on the same line
+    // if set, Tab/Shift-Tab never leave this widget once something inside it is focused
+    pub focus_scope: bool,
+    pub layout_hacks: LayoutHacks,
+}
+
+impl Widgetlike for FlowState {
+    fn create() -> Self {
+        FlowState {
+            widgets: SmallVec::new(),
+
+            spacing: 0,
+            focus_scope: false,
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, FlowState>) {
+        let plots = self.place(&menu.ui, brush.rect().width());
+
+        if self.focus_scope { menu.ui.begin_focus_scope(); }
+        for (w, p) in self.widgets.iter().zip(plots.iter()) {
+            w.draw(brush.region(*p), menu.share());
+        }
+        if self.focus_scope { menu.ui.end_focus_scope(); }
+    }
+
+    fn estimate_dimensions(&self, ui: &UI, width: isize) -> InternalWidgetDimensions {
+        let plots = self.place(ui, width);
+        let preferred_w = plots.iter().map(|p| p.max_x()).max().unwrap_or(0);
+        let preferred_h = plots.iter().map(|p| p.max_y()).max().unwrap_or(0);
+
+        // it can get as narrow as its widest child, but the height depends on the width
+        let min_w = self.widgets.iter().map(|w| w.estimate_dimensions(ui, width).min.width).max().unwrap_or(0);
+
+        InternalWidgetDimensions {
+            min: size2(min_w, preferred_h),
+            preferred: size2(preferred_w, preferred_h),
+            max: None,
+            align_size_to: size2(1, 1),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, ui: &UI) {
+        for w in self.widgets.iter() {
+            w.clear_layout_cache_if_needed(&ui)
+        }
+    }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl FlowState {
+    pub fn add<X: Into<AnyWidget>>(&mut self, w: X) {
+        self.widgets.push(w.into())
+    }
+
+    fn place(&self, ui: &UI, width: isize) -> SmallVec<[CellRect; SM]> {
+        let mut plots = SmallVec::new();
+        let (mut x, mut y, mut line_height) = (0, 0, 0);
+
+        for w in self.widgets.iter() {
+            let dims = w.estimate_dimensions(ui, width);
+            let size = size2(dims.preferred.width.min(width).max(dims.min.width), dims.preferred.height);
+
+            // wrap, unless this would be alone on its line anyway
+            if x > 0 && x + size.width > width {
+                x = 0;
+                y += line_height;
+                line_height = 0;
+            }
+            plots.push(rect(x, y, size.width, size.height));
+            x += size.width + self.spacing;
+            line_height = line_height.max(size.height);
+        }
+        plots
+    }
+}
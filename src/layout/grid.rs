@@ -0,0 +1,241 @@
+use std::cell::{Ref, RefCell};
+
+use chiropterm::{Brush, CellSize};
+use euclid::{rect, size2};
+use smallvec::SmallVec;
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetDimensions, WidgetMenu, Widgetlike, widget::{AnyWidget, LayoutHacks}};
+
+use super::flex::{Fallback, Flex, FlexItem, distribute};
+
+// Smallvec size -- set this to "more cells (or rows, or columns) than most users will ever put in one grid"
+const SM: usize = 32;
+
+pub type Grid = Widget<GridState>;
+
+struct GridCell {
+    row: usize,
+    col: usize,
+    row_span: usize,
+    col_span: usize,
+    widget: AnyWidget,
+}
+
+pub struct GridState {
+    cells: SmallVec<[GridCell; SM]>,
+    // prefix sums: where each column and row starts, plus where the last one ends
+    plots_practical: RefCell<(CellSize, Plots)>,
+
+    // if set, Tab/Shift-Tab never leave this widget once something inside it is focused
+    pub focus_scope: bool,
+    pub layout_hacks: LayoutHacks,
+}
+
+impl Widgetlike for GridState {
+    fn create() -> Self {
+        GridState {
+            cells: SmallVec::new(),
+            plots_practical: RefCell::new((size2(-1, -1), Plots::new())),
+
+            focus_scope: false,
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, GridState>) {
+        let plots = self.get_plots_practical(&menu.ui, brush.rect().size);
+        let plots = &plots.1;
+
+        if self.focus_scope { menu.ui.begin_focus_scope(); }
+        for c in self.cells.iter() {
+            let x = plots.col_x[c.col];
+            let y = plots.row_y[c.row];
+            let w = plots.col_x[c.col + c.col_span] - x;
+            let h = plots.row_y[c.row + c.row_span] - y;
+            c.widget.draw(brush.region(rect(x, y, w, h)), menu.share());
+        }
+        if self.focus_scope { menu.ui.end_focus_scope(); }
+    }
+
+    fn estimate_dimensions(&self, ui: &UI, width: isize) -> InternalWidgetDimensions {
+        let (cols, _, rows) = self.measure_tracks(ui, width);
+
+        InternalWidgetDimensions {
+            min: size2(cols.iter().map(|t| t.min).sum(), rows.iter().map(|t| t.min).sum()),
+            preferred: size2(cols.iter().map(|t| t.preferred).sum(), rows.iter().map(|t| t.preferred).sum()),
+            max: None,
+            align_size_to: size2(1, 1),
+            horizontal_spacer_count: cols.iter().map(|t| t.weight).sum(),
+            vertical_spacer_count: rows.iter().map(|t| t.weight).sum(),
+        }
+    }
+
+    fn clear_layout_cache(&self, ui: &UI) {
+        self.plots_practical.replace((size2(-1, -1), Plots::new()));
+        for c in self.cells.iter() {
+            c.widget.clear_layout_cache_if_needed(&ui)
+        }
+    }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl GridState {
+    pub fn add<X: Into<AnyWidget>>(&mut self, row: usize, col: usize, w: X) {
+        self.add_spanning(row, col, 1, 1, w)
+    }
+
+    pub fn add_spanning<X: Into<AnyWidget>>(&mut self, row: usize, col: usize, row_span: usize, col_span: usize, w: X) {
+        self.cells.push(GridCell { 
+            row, col, 
+            row_span: row_span.max(1), col_span: col_span.max(1), 
+            widget: w.into() 
+        });
+        self.plots_practical.replace((size2(-1, -1), Plots::new()));
+    }
+
+    fn n_cols(&self) -> usize {
+        self.cells.iter().map(|c| c.col + c.col_span).max().unwrap_or(0)
+    }
+
+    fn n_rows(&self) -> usize {
+        self.cells.iter().map(|c| c.row + c.row_span).max().unwrap_or(0)
+    }
+
+    fn estimate_cells(&self, ui: &UI, width: impl Fn(&GridCell) -> isize) -> SmallVec<[WidgetDimensions; SM]> {
+        self.cells.iter().map(|c| c.widget.estimate_dimensions(ui, width(c))).collect()
+    }
+
+    fn get_plots_practical(&self, ui: &UI, size: CellSize) -> Ref<'_, (CellSize, Plots)> {
+        {
+            let b = self.plots_practical.borrow();
+            if b.0 == size { return b }
+        }
+        self.plots_practical.replace((size, self.internal_compute_plots_practical(ui, size)));
+        return self.plots_practical.borrow()
+    }
+
+    fn internal_compute_plots_practical(&self, ui: &UI, size: CellSize) -> Plots {
+        let (_, col_x, rows) = self.measure_tracks(ui, size.width);
+        let row_y = prefix_sums(&fit(&rows, size.height));
+
+        Plots { col_x, row_y }
+    }
+
+    // The columns, where they start once fitted to the width, and the rows.
+    // Columns first: row heights depend on how wide everything ended up
+    fn measure_tracks(&self, ui: &UI, width: isize) -> (SmallVec<[Track; SM]>, SmallVec<[isize; SM]>, SmallVec<[Track; SM]>) {
+        let col_dims = self.estimate_cells(ui, |_| width);
+        let cols = measure(self.cells.iter().zip(col_dims.iter()).map(|(c, d)| 
+            (c.col, c.col_span, d.min.width, d.preferred.width, d.horizontal_spacer_count, d.align_size_to.width)
+        ), self.n_cols());
+        let col_x = prefix_sums(&fit(&cols, width));
+
+        let row_dims = self.estimate_cells(ui, |c| col_x[c.col + c.col_span] - col_x[c.col]);
+        let rows = measure(self.cells.iter().zip(row_dims.iter()).map(|(c, d)| 
+            (c.row, c.row_span, d.min.height, d.preferred.height, d.vertical_spacer_count, d.align_size_to.height)
+        ), self.n_rows());
+
+        (cols, col_x, rows)
+    }
+}
+
+struct Plots {
+    col_x: SmallVec<[isize; SM]>,
+    row_y: SmallVec<[isize; SM]>,
+}
+
+impl Plots {
+    fn new() -> Plots {
+        Plots { col_x: SmallVec::new(), row_y: SmallVec::new() }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Track {
+    min: isize,
+    preferred: isize,
+    weight: usize,  // spacer count: how much of any extra space it gets
+    align: isize,
+}
+
+impl Track {
+    fn flex_item(&self) -> FlexItem {
+        FlexItem { min: self.min, preferred: self.preferred, align: self.align, spacer_count: self.weight, flex: Flex::new() }
+    }
+}
+
+// Sizes each track (a row or column) to fit the cells in it.
+// Cells spanning several tracks only grow them if the tracks aren't already big enough
+fn measure(cells: impl Iterator<Item=(usize, usize, isize, isize, usize, isize)>, n: usize) -> SmallVec<[Track; SM]> {
+    let mut tracks: SmallVec<[Track; SM]> = (0..n).map(|_| Track { min: 0, preferred: 0, weight: 0, align: 1 }).collect();
+    let mut spanning: SmallVec<[(usize, usize, isize, isize); SM]> = SmallVec::new();
+
+    for (start, span, min, preferred, weight, align) in cells {
+        if span > 1 { 
+            spanning.push((start, span, min, preferred));
+            continue
+        }
+        let t = &mut tracks[start];
+        t.min = t.min.max(min);
+        t.preferred = t.preferred.max(preferred);
+        t.weight = t.weight.max(weight);
+        t.align = t.align.max(align.max(1));
+    }
+
+    for (start, span, min, preferred) in spanning {
+        let spanned = &mut tracks[start..start + span];
+        // the shortfall goes to the flexible tracks, or to all of them evenly if none are.
+        // Alignment is fixed up after, so don't let it leave any of the shortfall unfilled
+        let mins = spread(spanned, |t| t.min, min);
+        for (t, m) in spanned.iter_mut().zip(mins) { t.min = m }
+        let preferreds = spread(spanned, |t| t.preferred, preferred);
+        for (t, p) in spanned.iter_mut().zip(preferreds) { t.preferred = p }
+    }
+
+    for t in tracks.iter_mut() {
+        t.preferred = t.preferred.max(t.min);
+        // whole multiples, so two-cell-high text in neighbouring cells lines up
+        t.min += (t.align - t.min % t.align) % t.align;
+        t.preferred += (t.align - t.preferred % t.align) % t.align;
+    }
+    tracks
+}
+
+// Grows one size of each spanned track (its min or its preferred) until they add up to at least `needed`
+fn spread(spanned: &[Track], size: impl Fn(&Track) -> isize, needed: isize) -> SmallVec<[isize; SM]> {
+    let items: SmallVec<[FlexItem; SM]> = spanned.iter()
+        .map(|t| FlexItem { min: size(t), preferred: size(t), align: 1, ..t.flex_item() })
+        .collect();
+    let have: isize = items.iter().map(|i| i.preferred).sum();
+    distribute(&items, needed.max(have), Fallback::All)
+}
+
+// Like Row: extra space goes to the tracks with spacers (or everyone), and space is taken back from the end first
+fn fit(tracks: &[Track], available: isize) -> SmallVec<[isize; SM]> {
+    let items: SmallVec<[FlexItem; SM]> = tracks.iter().map(Track::flex_item).collect();
+    distribute(&items, available, Fallback::All)
+}
+
+fn prefix_sums(sizes: &[isize]) -> SmallVec<[isize; SM]> {
+    let mut sums = SmallVec::new();
+    let mut total = 0;
+    sums.push(0);
+    for s in sizes {
+        total += s;
+        sums.push(total);
+    }
+    sums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spanning_cells_only_grow_tracks_that_are_too_small() {
+        // (start, span, min, preferred, weight, align)
+        let tracks = measure(vec![(0, 1, 2, 3, 0, 1), (1, 1, 2, 3, 0, 1), (0, 2, 4, 10, 0, 1)].into_iter(), 2);
+        assert_eq!(tracks.iter().map(|t| (t.min, t.preferred)).collect::<Vec<_>>(), vec![(2, 5), (2, 5)]);
+    }
+}
@@ -1,17 +1,30 @@
 mod border;
 mod bulletin_board;
+mod children;
 mod column;
 mod container;
 mod deck;
+mod flex;
+mod flow;
+mod grid;
 mod row;
 mod scrollable;
 mod spacer;
+mod split_pane;
+mod tabbed_pane;
 
 pub use border::{Border, BorderState};
 pub use bulletin_board::{BulletinBoard, BulletinBoardState};
 pub use column::{Column, ColumnState};
 pub use container::{Container, ContainerState};
 pub use deck::{Deck, DeckState};
+pub use flex::Flex;
+pub(crate) use flex::{Fallback, FlexItem, distribute};
+pub use flow::{Flow, FlowState};
+pub use grid::{Grid, GridState};
 pub use row::{Row, RowState};
 pub use scrollable::{Scrollable, ScrollableState};
-pub use spacer::{Spacer, SpacerState};
\ No newline at end of file
+pub(crate) use scrollable::{Scrolls, draw_scrollbar, fix_offset};
+pub use spacer::{Spacer, SpacerState};
+pub use split_pane::{SplitDirection, SplitPane, SplitPaneState};
+pub use tabbed_pane::{TabbedPane, TabbedPaneState};
\ No newline at end of file
@@ -6,40 +6,48 @@ use smallvec::SmallVec;
 
 use crate::{InternalWidgetDimensions, UI, Widget, WidgetMenu, Widgetlike, widget::{AnyWidget, LayoutHacks}};
 
+use super::{children::Children, flex::{Fallback, Flex, FlexItem, distribute}};
+
 // Smallvec size -- set this to "higher than most users will ever put in one column/row"
 const SM: usize = 32;
 
 pub type Row = Widget<RowState>;
 
 pub struct RowState {
-    widgets: SmallVec<[AnyWidget; SM]>,
+    widgets: Children<Flex>,
     plots_desired: RefCell<(isize, (Plots, InternalWidgetDimensions))>,
     plots_practical: RefCell<(CellSize, Plots)>,
 
+    // if set, Tab/Shift-Tab never leave this widget once something inside it is focused
+    pub focus_scope: bool,
     pub layout_hacks: LayoutHacks,
 }
 
 impl Widgetlike for RowState {
     fn create() -> Self {
         RowState { 
-            widgets: SmallVec::new(),
+            widgets: Children::new(),
             plots_desired: RefCell::new((-1, (Plots::new(), InternalWidgetDimensions::zero()))),
             plots_practical: RefCell::new((size2(-1, -1), Plots::new())),
 
+            focus_scope: false,
             layout_hacks: LayoutHacks::new(),
         }
     }
 
     fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, RowState>) {
+        self.widgets.attach(&menu.ui);
         let plots = self.get_plots_practical(&menu.ui, brush.rect().size);
 
+        if self.focus_scope { menu.ui.begin_focus_scope(); }
         let mut total_x = 0;
         let height = brush.rect().height();
-        for (w, p) in self.widgets.iter().zip(plots.1.plot_size.iter()) {
+        for ((w, _), p) in self.widgets.iter().zip(plots.1.plot_size.iter()) {
             let real_plot = brush.region(rect(total_x, 0, *p, height));
             w.draw(real_plot.clone(), menu.share());
             total_x += p;
         }
+        if self.focus_scope { menu.ui.end_focus_scope(); }
     }
 
     fn estimate_dimensions(&self, ui: &UI, width: isize) -> InternalWidgetDimensions {
@@ -50,17 +58,66 @@ impl Widgetlike for RowState {
     fn clear_layout_cache(&self, ui: &UI) {
         self.plots_desired.replace((-1, (Plots::new(), InternalWidgetDimensions::zero())));
         self.plots_practical.replace((size2(-1, -1), Plots::new()));
-        for i in self.widgets.iter() {
-            i.clear_layout_cache_if_needed(&ui)
-        }
+        self.widgets.clear_layout_cache(ui);
     }
 
     fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
 }
 
 impl RowState {
+    pub fn len(&self) -> usize {
+        self.widgets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.widgets.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&AnyWidget> {
+        self.widgets.iter().map(|(w, _)| w)
+    }
+
+    pub fn clear(&mut self) {
+        self.widgets.clear()
+    }
+
     pub fn add<X: Into<AnyWidget>>(&mut self, w: X) {
-        self.widgets.push(w.into())
+        self.add_flex(w, Flex::new())
+    }
+
+    pub fn add_flex<X: Into<AnyWidget>>(&mut self, w: X, flex: Flex) {
+        self.widgets.push(w.into(), flex)
+    }
+
+    pub fn insert<X: Into<AnyWidget>>(&mut self, ix: usize, w: X) {
+        self.widgets.insert(ix, w.into(), Flex::new())
+    }
+
+    pub fn remove(&mut self, ix: usize) -> AnyWidget {
+        self.widgets.remove(ix)
+    }
+
+    // Returns false if the widget isn't a direct child
+    pub fn remove_widget<X: Into<AnyWidget>>(&mut self, w: X) -> bool {
+        self.widgets.remove_widget(&w.into())
+    }
+
+    // `to` is the index the widget ends up at
+    pub fn move_widget(&mut self, from: usize, to: usize) {
+        self.widgets.move_widget(from, to)
+    }
+
+    // Keeps the old widget's flex
+    pub fn replace<X: Into<AnyWidget>>(&mut self, ix: usize, w: X) -> AnyWidget {
+        self.widgets.replace(ix, w.into())
+    }
+
+    pub fn flex(&self, ix: usize) -> Flex {
+        *self.widgets.extra(ix)
+    }
+
+    pub fn set_flex(&mut self, ix: usize, flex: Flex) {
+        self.widgets.set_extra(ix, flex)
     }
 }
 
@@ -105,7 +162,7 @@ impl RowState {
         // with widgets: be as much of a spacer as the widgets inside
         let mut vertical_spacer_count = if self.widgets.len() > 0 { usize::MAX } else { 0 };
 
-        for w in self.widgets.iter() {
+        for (w, _) in self.widgets.iter() {
             let dim = w.estimate_dimensions(ui, width);
             preferred.push(dim.preferred.width);
 
@@ -133,66 +190,18 @@ impl RowState {
     }
 
     fn internal_compute_plots_practical(&self, ui: &UI, size: CellSize) -> Plots {
-        let mut likes_being_resized: SmallVec<[usize; SM]> = SmallVec::new();
-        let mut minimum: SmallVec<[isize; SM]> = SmallVec::new();
-        let mut practical: SmallVec<[isize; SM]> = SmallVec::new();
-        let mut align: SmallVec<[isize; SM]> = SmallVec::new();
-
-        for (w, widg) in self.widgets.iter().enumerate() {
-            let dim = widg.estimate_dimensions(ui, size.width);
-            for _ in 0..dim.horizontal_spacer_count {
-                likes_being_resized.push(w)
-            }
-            minimum.push(dim.min.width);
-            practical.push(dim.preferred.width);
-            align.push(dim.align_size_to.width);
-        }
-
-        if practical.len() == 0 || size.width < 0 { return Plots { plot_size: practical }; }
-
-        let mut practical_sum: isize = practical.iter().sum();
-        if practical_sum < size.width {
-            // Expand whatever likes being resized
-            if likes_being_resized.len() == 0 {
-                // Expand every cell
-                likes_being_resized.extend(0..self.widgets.len())
-            }
-
-            let og_rem = (size.width - practical_sum) as usize;
-            let mut current_rem = og_rem;
-            let portion = og_rem / likes_being_resized.len();
-            for i in likes_being_resized.iter() {
-                practical[*i] += portion as isize;
-                current_rem -= portion;
-            };
-            for i in likes_being_resized.iter().take(current_rem as usize) {
-                practical[*i] += 1;
+        let items: SmallVec<[FlexItem; SM]> = self.widgets.iter().map(|(w, flex)| {
+            let dim = w.estimate_dimensions(ui, size.width);
+            FlexItem {
+                min: dim.min.width,
+                preferred: dim.preferred.width,
+                align: dim.align_size_to.width,
+                spacer_count: dim.horizontal_spacer_count,
+                flex: *flex,
             }
-        }
-        else {
-            let mut desperate = false;
-
-            'fix: while practical_sum > size.width {
-                // Steal from everyone equally, starting at bottom
-                let prev_sum = practical_sum;
-                for i in (0..practical.len()).rev() {
-                    if !desperate && practical[i] <= minimum[i] { continue }
-                    if practical[i] <= 0 { continue }
-
-                    practical[i] -= align[i];
-                    practical_sum -= align[i];
-                    if practical_sum <= size.width { 
-                        break 'fix; 
-                    }
-                }
-                if prev_sum == practical_sum { 
-                    desperate = true; 
-                }
-            }
-
-        }
+        }).collect();
 
-        return Plots { plot_size: practical }
+        Plots { plot_size: distribute(&items, size.width, Fallback::All) }
     }
 }
 
@@ -17,6 +17,15 @@ pub struct ScrollableState {
     pub layout_hacks: LayoutHacks,
 }
 
+// Implemented by widgets that draw their own content but borrow the scrollbar from Scrollable
+pub(crate) trait Scrolls: Widgetlike {
+    fn scroll_offset(&self) -> &Cell<f64>;
+}
+
+impl Scrolls for ScrollableState {
+    fn scroll_offset(&self) -> &Cell<f64> { &self.offset }
+}
+
 impl Widgetlike for ScrollableState {
     fn create() -> Self {
         ScrollableState { 
@@ -34,123 +43,14 @@ impl Widgetlike for ScrollableState {
             let inner_height = dims.preferred.height;
             let brush_height = brush.rect().height();
 
-            let offset_to_use = self.fix_offset(inner_height, brush_height);
+            let offset_to_use = fix_offset(&self.offset, inner_height, brush_height);
 
             let space_to_adjust = (inner_height - brush_height).max(0);
 
             if space_to_adjust > 0 {
                 let inner_width = brush.rect().width() - 2;
 
-                let scrollbar = brush.region(rect(brush.rect().width() - 2, 0, 2, brush_height));
-
-                let top_button = scrollbar.region(rect(0, 0, 2, 2));
-                let btm_button = scrollbar.region(rect(0, scrollbar.rect().height() - 2, 2, 2));
-
-                let scrollable_height = scrollbar.rect().height() - 4;
-
-                let position_top = if space_to_adjust == 0 { 0.0 } else { offset_to_use as f64 / inner_height as f64 };
-                let ix_top = (scrollable_height as f64 * position_top).floor() as isize;
-                let barpart_height = if inner_height == 0 { 1 } else { 
-                    (((brush_height as f64 / inner_height as f64) * scrollable_height as f64).ceil() as isize)
-                    .max(1).min(scrollable_height) 
-                };
-
-                let scroll_offset_for = move |dy: f32| {
-                    // TODO: This calculation is subtly wrong but I don't know why yet.
-                    if scrollable_height == 0 { return 0.0; }
-                    let scrolls_per_cell = inner_height as f64 / scrollable_height as f64;
-                    dy as f64 * scrolls_per_cell
-                };
-
-                let mut ix_bot = ix_top + barpart_height;
-
-                if ix_bot == ix_top { ix_bot += 1; }
-
-                let scrollbar_rect = rect(0, ix_top + 2, 2, ix_bot - ix_top);
-
-                let top_button_interactor = menu.on_mouse(move |_, w, me| {
-                    match me {
-                        MouseEvent::Click(MouseButton::Left, _, _) => { 
-                            w.unique.set_offset(w.unique.offset.get() - scroll_offset_for(1 as f32).max(2.0), inner_height, brush_height); 
-                            return Signal::Refresh;
-                        }
-                        MouseEvent::Click(_, _, _) => {}
-                        MouseEvent::Up(_, _, _) => {}
-                        MouseEvent::Drag { .. } => {}
-                        MouseEvent::Scroll(_, _, _) => {}
-                        MouseEvent::Wiggle {..} => {}
-                    };
-                    Signal::Continue
-                });
-
-                let btm_button_interactor = menu.on_mouse(move |_, w, me| {
-                    match me {
-                        MouseEvent::Click(MouseButton::Left, _, _) => { 
-                            w.unique.set_offset(w.unique.offset.get() + scroll_offset_for(1 as f32).max(2.0), inner_height, brush_height); 
-                            return Signal::Refresh;
-                        }
-                        MouseEvent::Click(_, _, _) => {}
-                        MouseEvent::Up(_, _, _) => {}
-                        MouseEvent::Drag { .. } => {}
-                        MouseEvent::Scroll(_, _, _) => {}
-                        MouseEvent::Wiggle {..} => {}
-                    };
-                    Signal::Continue
-                });
-
-                let bar_interactor = menu.on_mouse(move |_, w, me| {
-                    // let me = me.offset(size2(scrollbar_rect.min_x() - brush.rect().min_x(), 0));
-                    // may be no need, X doesn't matter
-                    match me {
-                        MouseEvent::Click(MouseButton::Left, point, _) => {
-                            let scrollbar_center = (ix_top + ix_bot) / 2;
-                            w.unique.set_offset(
-                                w.unique.offset.get() + scroll_offset_for((point.y - scrollbar_center) as f32),
-                                    inner_height, brush_height,
-                            ); 
-                            return Signal::Refresh;
-                        }
-                        MouseEvent::Click(_, _, _) => {}
-                        MouseEvent::Up(_, _, _) => {}
-                        MouseEvent::Drag { 
-                            mouse_button: MouseButton::Left,
-                            last_point,
-                            now_point,
-                            .. 
-                        } => {
-                            w.unique.set_offset(
-                                w.unique.offset.get() + scroll_offset_for((now_point.y - last_point.y) as f32),
-                                inner_height, brush_height,
-                            );
-                            return Signal::Refresh;
-                        }
-                        MouseEvent::Drag { .. } => {} 
-                        MouseEvent::Scroll(amt, _, _) => {
-                            w.unique.set_offset(
-                                w.unique.offset.get() + scroll_offset_for(amt),
-                                inner_height, brush_height,
-                            );
-                            return Signal::Refresh;
-                        },
-                        MouseEvent::Wiggle { .. } => {}
-                    }
-                    Signal::Continue
-                });
-
-                let sb_brush = scrollbar.interactor(bar_interactor, menu.ui.theme().input_box.selected);
-                sb_brush.fill(FSem::new().color(menu.ui.theme().input_box.deselected));
-                sb_brush.bevel_w95(menu.ui.theme().input_box.bevel);
-
-                let scrollbar_region = scrollbar.region(scrollbar_rect);
-                scrollbar_region.bevel_w95(menu.ui.theme().button.bevel);
-                scrollbar_region.interactor(bar_interactor, menu.ui.theme().input_box.cursor).fill(FSem::new().color(menu.ui.theme().input_box.cursor));
-
-                top_button.bevel_w95(menu.ui.theme().button.bevel);
-                btm_button.bevel_w95(menu.ui.theme().button.bevel);
-                top_button.interactor(top_button_interactor, menu.ui.theme().button.preclick).font(Font::Set).putch(0x1eu16);
-                btm_button.interactor(btm_button_interactor, menu.ui.theme().button.preclick).font(Font::Set).putch(0x1fu16);
-
-                brush.dont_interfere_with_interactor().scroll_interactor(bar_interactor).fill(FSem::new());
+                draw_scrollbar(brush.clone(), &menu, inner_height, offset_to_use);
 
                 w.draw(
                     brush.region(
@@ -192,21 +92,143 @@ impl Widgetlike for ScrollableState {
     }
 }
 
-impl ScrollableState {
-    fn fix_offset(&self, inner_height: isize, brush_height: isize) -> isize {
-        let space_to_adjust = (inner_height - brush_height).max(0);
-
-        let new_offset = self.offset.get().max(0.0).min(space_to_adjust as f64);
-        self.offset.replace(new_offset);
-        let mut offset_to_use = self.offset.get() as isize;
-        offset_to_use -= offset_to_use % 2;
-        offset_to_use
-    }
+pub(crate) fn fix_offset(offset: &Cell<f64>, inner_height: isize, brush_height: isize) -> isize {
+    let space_to_adjust = (inner_height - brush_height).max(0);
 
-    fn set_offset(&self, new_value: f64, inner_height: isize, brush_height: isize) {
-        self.offset.replace(new_value);
-        self.fix_offset(inner_height, brush_height);
-    }
+    let new_offset = offset.get().max(0.0).min(space_to_adjust as f64);
+    offset.replace(new_offset);
+    let mut offset_to_use = offset.get() as isize;
+    offset_to_use -= offset_to_use % 2;
+    offset_to_use
+}
+
+pub(crate) fn set_offset(offset: &Cell<f64>, new_value: f64, inner_height: isize, brush_height: isize) {
+    offset.replace(new_value);
+    fix_offset(offset, inner_height, brush_height);
+}
+
+// Draws a scrollbar in the rightmost two columns of `brush` and makes the whole brush respond to the wheel.
+// Draw the content afterwards, in the remaining width.
+pub(crate) fn draw_scrollbar<'frame, T: Scrolls>(brush: Brush, menu: &WidgetMenu<'frame, T>, inner_height: isize, offset_to_use: isize) {
+    let brush_height = brush.rect().height();
+    let space_to_adjust = (inner_height - brush_height).max(0);
+
+    let scrollbar = brush.region(rect(brush.rect().width() - 2, 0, 2, brush_height));
+
+    let top_button = scrollbar.region(rect(0, 0, 2, 2));
+    let btm_button = scrollbar.region(rect(0, scrollbar.rect().height() - 2, 2, 2));
+
+    let scrollable_height = scrollbar.rect().height() - 4;
+
+    let position_top = if space_to_adjust == 0 { 0.0 } else { offset_to_use as f64 / inner_height as f64 };
+    let ix_top = (scrollable_height as f64 * position_top).floor() as isize;
+    let barpart_height = if inner_height == 0 { 1 } else { 
+        (((brush_height as f64 / inner_height as f64) * scrollable_height as f64).ceil() as isize)
+        .max(1).min(scrollable_height) 
+    };
+
+    let scroll_offset_for = move |dy: f32| {
+        // TODO: This calculation is subtly wrong but I don't know why yet.
+        if scrollable_height == 0 { return 0.0; }
+        let scrolls_per_cell = inner_height as f64 / scrollable_height as f64;
+        dy as f64 * scrolls_per_cell
+    };
+
+    let mut ix_bot = ix_top + barpart_height;
+
+    if ix_bot == ix_top { ix_bot += 1; }
+
+    let scrollbar_rect = rect(0, ix_top + 2, 2, ix_bot - ix_top);
+
+    let top_button_interactor = menu.on_mouse(move |_, w, me| {
+        match me {
+            MouseEvent::Click(MouseButton::Left, _, _) => { 
+                let offset = w.unique.scroll_offset();
+                set_offset(offset, offset.get() - scroll_offset_for(1 as f32).max(2.0), inner_height, brush_height); 
+                return Signal::Refresh;
+            }
+            MouseEvent::Click(_, _, _) => {}
+            MouseEvent::Up(_, _, _) => {}
+            MouseEvent::Drag { .. } => {}
+            MouseEvent::Scroll(_, _, _) => {}
+            MouseEvent::Wiggle {..} => {}
+        };
+        Signal::Continue
+    });
+
+    let btm_button_interactor = menu.on_mouse(move |_, w, me| {
+        match me {
+            MouseEvent::Click(MouseButton::Left, _, _) => { 
+                let offset = w.unique.scroll_offset();
+                set_offset(offset, offset.get() + scroll_offset_for(1 as f32).max(2.0), inner_height, brush_height); 
+                return Signal::Refresh;
+            }
+            MouseEvent::Click(_, _, _) => {}
+            MouseEvent::Up(_, _, _) => {}
+            MouseEvent::Drag { .. } => {}
+            MouseEvent::Scroll(_, _, _) => {}
+            MouseEvent::Wiggle {..} => {}
+        };
+        Signal::Continue
+    });
+
+    let bar_interactor = menu.on_mouse(move |_, w, me| {
+        // let me = me.offset(size2(scrollbar_rect.min_x() - brush.rect().min_x(), 0));
+        // may be no need, X doesn't matter
+        let offset = w.unique.scroll_offset();
+        match me {
+            MouseEvent::Click(MouseButton::Left, point, _) => {
+                let scrollbar_center = (ix_top + ix_bot) / 2;
+                set_offset(
+                    offset,
+                    offset.get() + scroll_offset_for((point.y - scrollbar_center) as f32),
+                    inner_height, brush_height,
+                ); 
+                return Signal::Refresh;
+            }
+            MouseEvent::Click(_, _, _) => {}
+            MouseEvent::Up(_, _, _) => {}
+            MouseEvent::Drag { 
+                mouse_button: MouseButton::Left,
+                last_point,
+                now_point,
+                .. 
+            } => {
+                set_offset(
+                    offset,
+                    offset.get() + scroll_offset_for((now_point.y - last_point.y) as f32),
+                    inner_height, brush_height,
+                );
+                return Signal::Refresh;
+            }
+            MouseEvent::Drag { .. } => {} 
+            MouseEvent::Scroll(amt, _, _) => {
+                set_offset(
+                    offset,
+                    offset.get() + scroll_offset_for(amt),
+                    inner_height, brush_height,
+                );
+                return Signal::Refresh;
+            },
+            MouseEvent::Wiggle { .. } => {}
+        }
+        Signal::Continue
+    });
+
+    let sb_brush = scrollbar.interactor(bar_interactor, menu.ui.theme().input_box.selected);
+    sb_brush.fill(FSem::new().color(menu.ui.theme().input_box.deselected));
+    sb_brush.bevel_w95(menu.ui.theme().input_box.bevel);
+
+    let scrollbar_region = scrollbar.region(scrollbar_rect);
+    scrollbar_region.bevel_w95(menu.ui.theme().button.bevel);
+    scrollbar_region.interactor(bar_interactor, menu.ui.theme().input_box.cursor).fill(FSem::new().color(menu.ui.theme().input_box.cursor));
+
+    top_button.bevel_w95(menu.ui.theme().button.bevel);
+    btm_button.bevel_w95(menu.ui.theme().button.bevel);
+    top_button.interactor(top_button_interactor, menu.ui.theme().button.preclick).font(Font::Set).putch(0x1eu16);
+    btm_button.interactor(btm_button_interactor, menu.ui.theme().button.preclick).font(Font::Set).putch(0x1fu16);
+
+    brush.dont_interfere_with_interactor().scroll_interactor(bar_interactor).fill(FSem::new());
 }
 
 impl ScrollableState {
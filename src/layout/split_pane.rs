@@ -0,0 +1,200 @@
+use std::cell::Cell;
+
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetDimensions, WidgetMenu, Widgetlike, widget::{AnyWidget, LayoutHacks}};
+
+pub type SplitPane = Widget<SplitPaneState>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SplitDirection {
+    Horizontal,  // side by side
+    Vertical,  // stacked
+}
+
+pub struct SplitPaneState {
+    first: Option<AnyWidget>,
+    second: Option<AnyWidget>,
+    divider: Option<isize>,  // space given to the first widget. None: split by preferred size
+    last_divider: Cell<isize>,  // where it was drawn last frame, after clamping
+    drag_start: isize,
+
+    pub direction: SplitDirection,
+    pub layout_hacks: LayoutHacks,
+}
+
+impl SplitPaneState {
+    pub fn set_first<X: Into<AnyWidget>>(&mut self, w: X) {
+        self.first = Some(w.into())
+    }
+
+    pub fn set_second<X: Into<AnyWidget>>(&mut self, w: X) {
+        self.second = Some(w.into())
+    }
+
+    pub fn divider_position(&self) -> isize {
+        self.last_divider.get()
+    }
+
+    pub fn set_divider_position(&mut self, position: isize) {
+        self.divider = Some(position.max(0));
+        self.last_divider.set(position.max(0));
+    }
+
+    fn thickness(&self) -> isize {
+        // keep two-cell-high text in the bottom pane lined up
+        match self.direction {
+            SplitDirection::Horizontal => 1,
+            SplitDirection::Vertical => 2,
+        }
+    }
+
+    fn along(&self, size: CellSize) -> isize {
+        match self.direction {
+            SplitDirection::Horizontal => size.width,
+            SplitDirection::Vertical => size.height,
+        }
+    }
+
+    fn dims(&self, ui: &UI, w: &Option<AnyWidget>, width: isize) -> WidgetDimensions {
+        match w {
+            Some(w) => w.estimate_dimensions(ui, width),
+            None => InternalWidgetDimensions::zero().to_external(),
+        }
+    }
+
+    // Honors both widgets' minimum sizes if there's room, favoring the first if there isn't
+    fn clamp_divider(&self, ui: &UI, size: CellSize) -> isize {
+        let first = self.dims(ui, &self.first, size.width);
+        let second = self.dims(ui, &self.second, size.width);
+        let total = self.along(size) - self.thickness();
+
+        let wanted = self.divider.unwrap_or(self.along(first.preferred));
+        let align = self.along(first.align_size_to).max(1);
+        let max = (total - self.along(second.min)).max(self.along(first.min));
+        let pos = wanted.min(max).max(self.along(first.min)).min(total).max(0);
+        pos - pos % align
+    }
+
+    fn nudge(&mut self, amount: isize) {
+        let step = self.thickness();
+        self.divider = Some((self.last_divider.get() + amount * step).max(0));
+    }
+}
+
+impl Widgetlike for SplitPaneState {
+    fn create() -> Self {
+        SplitPaneState {
+            first: None,
+            second: None,
+            divider: None,
+            last_divider: Cell::new(0),
+            drag_start: 0,
+
+            direction: SplitDirection::Horizontal,
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, selected: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        menu.register_focus();
+
+        let (less, more) = match self.direction {
+            SplitDirection::Horizontal => (Keycode::Left, Keycode::Right),
+            SplitDirection::Vertical => (Keycode::Up, Keycode::Down),
+        };
+        if selected {
+            for (key, amount) in [(less, -1), (more, 1)] {
+                menu.on_key_hprio(OnKey::only(key).pressed(), move |_, this, _| {
+                    this.unique.nudge(amount);
+                    Signal::Refresh
+                });
+            }
+        }
+
+        let divider_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+            match click {
+                MouseEvent::Click(MouseButton::Left, _, _) => {
+                    ui.select(this);
+                    this.unique.drag_start = this.unique.last_divider.get();
+                    return Signal::Refresh
+                }
+                MouseEvent::Click(_, _, _) => {}
+                MouseEvent::Up(_, _, _) => {}
+                MouseEvent::Drag { mouse_button: MouseButton::Left, start_point, now_point, .. } => {
+                    let delta = match this.unique.direction {
+                        SplitDirection::Horizontal => now_point.x - start_point.x,
+                        SplitDirection::Vertical => now_point.y - start_point.y,
+                    };
+                    this.unique.divider = Some((this.unique.drag_start + delta).max(0));
+                    return Signal::Refresh
+                }
+                MouseEvent::Drag {..} => {}
+                MouseEvent::Scroll(_, _, _) => {}
+                MouseEvent::Wiggle {..} => {}
+            };
+            Signal::Continue
+        });
+
+        let size = brush.rect().size;
+        let pos = self.clamp_divider(&menu.ui, size);
+        self.last_divider.set(pos);
+
+        let t = self.thickness();
+        let (first, divider, second) = match self.direction {
+            SplitDirection::Horizontal => (
+                rect(0, 0, pos, size.height),
+                rect(pos, 0, t, size.height),
+                rect(pos + t, 0, size.width - pos - t, size.height),
+            ),
+            SplitDirection::Vertical => (
+                rect(0, 0, size.width, pos),
+                rect(0, pos, size.width, t),
+                rect(0, pos + t, size.width, size.height - pos - t),
+            ),
+        };
+
+        if let Some(w) = &self.first { w.draw(brush.region(first), menu.share()) }
+        if let Some(w) = &self.second { w.draw(brush.region(second), menu.share()) }
+
+        let theme = menu.ui.theme().button;
+        let divider = brush.region(divider);
+        divider.fill(FSem::new().sem(SemanticContent::Blank).color(if selected { theme.focused } else { theme.color }));
+        divider.bevel_w95(theme.bevel);
+        divider.interactor(divider_interactor, theme.preclick).fill(FSem::new());
+    }
+
+    fn estimate_dimensions(&self, ui: &UI, width: isize) -> InternalWidgetDimensions {
+        let first = self.dims(ui, &self.first, width);
+        let second = self.dims(ui, &self.second, width);
+        let t = self.thickness();
+
+        let (min, preferred) = match self.direction {
+            SplitDirection::Horizontal => (
+                size2(first.min.width + t + second.min.width, first.min.height.max(second.min.height)),
+                size2(first.preferred.width + t + second.preferred.width, first.preferred.height.max(second.preferred.height)),
+            ),
+            SplitDirection::Vertical => (
+                size2(first.min.width.max(second.min.width), first.min.height + t + second.min.height),
+                size2(first.preferred.width.max(second.preferred.width), first.preferred.height + t + second.preferred.height),
+            ),
+        };
+
+        InternalWidgetDimensions {
+            min,
+            preferred,
+            max: None,
+            align_size_to: size2(1, 1),
+            horizontal_spacer_count: first.horizontal_spacer_count.max(second.horizontal_spacer_count),
+            vertical_spacer_count: first.vertical_spacer_count.max(second.vertical_spacer_count),
+        }
+    }
+
+    fn clear_layout_cache(&self, ui: &UI) {
+        if let Some(w) = &self.first { w.clear_layout_cache_if_needed(ui) }
+        if let Some(w) = &self.second { w.clear_layout_cache_if_needed(ui) }
+    }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
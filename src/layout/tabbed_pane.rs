@@ -0,0 +1,247 @@
+use chiropterm::*;
+use euclid::{rect, size2};
+use smallvec::SmallVec;
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetCommon, WidgetMenu, Widgetlike, input::text_width, look_and_feel::WindowBorders, widget::{AnyWidget, LayoutHacks}};
+
+// Smallvec size -- set this to "more tabs than most users will ever open in one pane"
+const SM: usize = 32;
+
+const STRIP_HEIGHT: isize = 2;
+
+pub type TabbedPane = Widget<TabbedPaneState>;
+
+struct Tab {
+    title: String,
+    widget: AnyWidget,
+    closable: bool,
+}
+
+pub struct TabbedPaneState {
+    tabs: SmallVec<[Tab; SM]>,
+    active: usize,
+
+    // runs before the tab goes, so it can still look at it. Return false to keep it open
+    pub on_close: Option<Box<dyn FnMut(UI, &mut WidgetCommon<TabbedPaneState>, usize) -> bool>>,
+    pub window_border_override: Option<WindowBorders>,
+    pub layout_hacks: LayoutHacks,
+}
+
+impl TabbedPaneState {
+    pub fn add<X: Into<AnyWidget>>(&mut self, title: impl Into<String>, w: X) -> usize {
+        self.tabs.push(Tab { title: title.into(), widget: w.into(), closable: false });
+        self.tabs.len() - 1
+    }
+
+    pub fn add_closable<X: Into<AnyWidget>>(&mut self, title: impl Into<String>, w: X) -> usize {
+        self.tabs.push(Tab { title: title.into(), widget: w.into(), closable: true });
+        self.tabs.len() - 1
+    }
+
+    pub fn remove(&mut self, ix: usize) -> AnyWidget {
+        let tab = self.tabs.remove(ix);
+        if self.active > ix || self.active >= self.tabs.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        tab.widget
+    }
+
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    pub fn set_active(&mut self, ix: usize) {
+        if ix < self.tabs.len() { self.active = ix; }
+    }
+
+    pub fn title(&self, ix: usize) -> &str {
+        &self.tabs[ix].title
+    }
+
+    pub fn set_title(&mut self, ix: usize, title: impl Into<String>) {
+        self.tabs[ix].title = title.into();
+    }
+
+    pub fn widget(&self, ix: usize) -> &AnyWidget {
+        &self.tabs[ix].widget
+    }
+
+    pub fn set_on_close(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<TabbedPaneState>, usize) -> bool) {
+        self.on_close = Some(Box::new(cmd))
+    }
+}
+
+impl Widgetlike for TabbedPaneState {
+    fn create() -> Self {
+        TabbedPaneState {
+            tabs: SmallVec::new(),
+            active: 0,
+
+            on_close: None,
+            window_border_override: None,
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        if self.tabs.len() == 0 { return }
+
+        let theme = menu.ui.theme();
+        let borders = self.window_border_override.unwrap_or(theme.window.borders);
+        let width = brush.rect().width();
+        let height = brush.rect().height();
+
+        // the page first, so the active tab can paint over its top edge
+        let page = brush.region(rect(0, STRIP_HEIGHT, width, height - STRIP_HEIGHT));
+        page.fill(FSem::new().sem(SemanticContent::Blank).color(theme.window.color));
+        let inner = match borders {
+            WindowBorders::W95 { bevel, .. } => {
+                page.bevel_w95(bevel);
+                brush.region(rect(1, STRIP_HEIGHT, width - 2, height - STRIP_HEIGHT - 1))
+            }
+            WindowBorders::DOS { border, border_double, .. } => {
+                page.fg(border).draw_box(border_double);
+                page.region(page.rect().inflate(-2, -2))
+            }
+        };
+
+        let mut x = 0;
+        for (i, tab) in self.tabs.iter().enumerate() {
+            let tab_width = tab_width(tab).min(width - x);
+            if tab_width <= 0 { break }
+
+            let select_interactor = menu.on_mouse(move |_, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        this.unique.set_active(i);
+                        return Signal::Refresh
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+
+            let is_active = i == self.active;
+            let tab_brush = brush.region(rect(x, 0, tab_width, STRIP_HEIGHT));
+            let title_brush = match borders {
+                WindowBorders::W95 { bevel, .. } => {
+                    tab_brush.fill(FSem::new().sem(SemanticContent::Blank).color(theme.window.color));
+                    tab_brush.bevel_w95(bevel);
+                    if is_active {
+                        // merge into the page below
+                        tab_brush.bevel_bottom(255);
+                    }
+                    tab_brush.region(rect(1, 0, tab_width - 1, STRIP_HEIGHT))
+                }
+                WindowBorders::DOS { border, active_title_fg, inactive_title_fg, .. } => {
+                    let fg = if is_active { active_title_fg } else { inactive_title_fg };
+                    tab_brush.fg(border).putch(if is_active { 0x10u16 } else { ' ' as u16 });  // CP437 right-pointing triangle
+                    tab_brush.region(rect(1, 0, tab_width - 1, STRIP_HEIGHT)).fg(fg)
+                }
+            };
+            title_brush.interactor(select_interactor, theme.button.preclick).putfs(&tab.title);
+
+            if tab.closable {
+                let close_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+                    match click {
+                        MouseEvent::Click(MouseButton::Left, _, _) => {
+                            return TabbedPaneState::close(ui, this, i)
+                        }
+                        MouseEvent::Click(_, _, _) => {}
+                        MouseEvent::Up(_, _, _) => {}
+                        MouseEvent::Drag {..} => {}
+                        MouseEvent::Scroll(_, _, _) => {}
+                        MouseEvent::Wiggle {..} => {}
+                    };
+                    Signal::Continue
+                });
+                tab_brush.region(rect(tab_width - 2, 0, 1, STRIP_HEIGHT))
+                    .interactor(close_interactor, theme.button.preclick).putch('x' as u16);
+            }
+            x += tab_width;
+        }
+
+        let mark = menu.ui.focus_mark();
+        self.tabs[self.active].widget.draw(inner, menu.share());
+
+        // switching tabs from the keyboard is for the innermost pane around the focus, not every pane on screen
+        if menu.ui.claim_focus(mark) {
+            let step = |amount: isize| move |_: UI, this: &mut WidgetCommon<TabbedPaneState>, _: KeyEvent| {
+                let n = this.unique.tabs.len() as isize;
+                this.unique.active = (this.unique.active as isize + amount).rem_euclid(n) as usize;
+                Signal::Refresh
+            };
+            menu.on_key(OnKey::only(Keycode::Tab).control().pressed(), step(1));
+            menu.on_key(OnKey::only(Keycode::Tab).control().shift().pressed(), step(-1));
+            menu.on_key(OnKey::only(Keycode::PageDown).control().pressed(), step(1));
+            menu.on_key(OnKey::only(Keycode::PageUp).control().pressed(), step(-1));
+        }
+    }
+
+    fn estimate_dimensions(&self, ui: &UI, width: isize) -> InternalWidgetDimensions {
+        let borders = self.window_border_override.unwrap_or(ui.theme().window.borders);
+        let (pad_x, pad_y) = match borders {
+            WindowBorders::W95 { .. } => (2, STRIP_HEIGHT + 1),
+            WindowBorders::DOS { .. } => (4, STRIP_HEIGHT + 4),
+        };
+
+        // big enough for any page, so switching tabs doesn't move everything else around
+        let mut min = size2(0, 0);
+        let mut preferred = size2(0, 0);
+        for tab in self.tabs.iter() {
+            let dims = tab.widget.estimate_dimensions(ui, width - pad_x);
+            min = min.max(dims.min);
+            preferred = preferred.max(dims.preferred);
+        }
+        let strip_width: isize = self.tabs.iter().map(tab_width).sum();
+        preferred.width = preferred.width.max(strip_width - pad_x);
+
+        let size = InternalWidgetDimensions { 
+            min,
+            preferred,
+            max: None,
+            align_size_to: size2(1, 1),
+            // don't pass spacers through
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0
+        };
+        size.increase(size2(pad_x, pad_y))
+    }
+
+    fn clear_layout_cache(&self, ui: &UI) {
+        for t in self.tabs.iter() {
+            t.widget.clear_layout_cache_if_needed(&ui)
+        }
+    }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+impl TabbedPaneState {
+    fn close(ui: UI, this: &mut WidgetCommon<Self>, ix: usize) -> Signal {
+        let on_close = this.unique.on_close.take();
+        if let Some(mut c) = on_close {
+            let close = c(ui.share(), this, ix);
+            // unless the callback installed a new one
+            if this.unique.on_close.is_none() { this.unique.on_close = Some(c) }
+            if !close || ix >= this.unique.tabs.len() { return Signal::Refresh }
+        }
+
+        this.unique.remove(ix);
+        ui.recompute_layout();
+        Signal::Refresh
+    }
+}
+
+fn tab_width(tab: &Tab) -> isize {
+    // a cell of padding on either side, plus room for the close button
+    text_width(&tab.title) as isize + 2 + if tab.closable { 2 } else { 0 }
+}
@@ -1,15 +1,22 @@
+pub(self) mod collection;
+pub(self) mod dialog;
 pub(self) mod display;
+pub mod headless;
 pub(self) mod input;
 pub(self) mod layout;
 pub mod look_and_feel;
+pub(self) mod menus;
 pub(self) mod ui;
 pub(self) mod widget;
 pub(self) mod window;
 
+pub use collection::*;
+pub use dialog::*;
 pub use display::*;
 pub use input::*;
 pub use layout::*;
 pub use look_and_feel::Theme;
-pub use ui::{Selection, UI, UISource};
-pub use widget::{AnyWidget, InternalWidgetDimensions, LayoutHacks, Widget, WidgetCommon, WidgetDimensions, Widgetlike, WidgetMenu};
+pub use menus::*;
+pub use ui::{Clipboard, LocalClipboard, OverlayOptions, Selection, UI, UISource};
+pub use widget::{Align, AnyWidget, InternalWidgetDimensions, LayoutHacks, Sides, Widget, WidgetCommon, WidgetDimensions, Widgetlike, WidgetMenu};
 pub use window::*;
\ No newline at end of file
@@ -8,6 +8,11 @@ pub struct Theme {
     pub window: WindowTheme,
     pub button: ButtonTheme,
     pub input_box: InputBoxTheme,
+    pub checkbox: ToggleTheme,
+    pub radio_group: ToggleTheme,
+    pub list: ListTheme,
+    pub menu: MenuTheme,
+    pub dialog: DialogTheme,
 }
 
 #[derive(Clone, Copy)]
@@ -41,6 +46,7 @@ pub enum WindowBorders {
 pub struct ButtonTheme {
     pub bevel: (u8, u8),
     pub color: (u8, u8),
+    pub focused: (u8, u8),
     pub preclick: (u8, u8),
 }
 
@@ -51,4 +57,38 @@ pub struct InputBoxTheme {
     pub selected: (u8, u8),
     pub preclick: (u8, u8),
     pub cursor: (u8, u8),
+    pub invalid: (u8, u8),  // text the validator called Invalid. Rejected text never gets in
+}
+
+#[derive(Clone, Copy)]
+pub struct ToggleTheme {
+    pub bevel: (u8, u8),
+    pub box_color: (u8, u8),
+    pub focused: (u8, u8),
+    pub preclick: (u8, u8),
+}
+
+#[derive(Clone, Copy)]
+pub struct ListTheme {
+    pub bevel: (u8, u8),
+    pub color: (u8, u8),
+    pub selected: (u8, u8),
+    pub cursor: (u8, u8),
+    pub preclick: (u8, u8),
+}
+
+#[derive(Clone, Copy)]
+pub struct MenuTheme {
+    pub bar: (u8, u8),
+    pub bevel: (u8, u8),
+    pub color: (u8, u8),
+    pub highlighted: (u8, u8),
+    pub preclick: (u8, u8),
+    pub disabled_fg: u8,
+    pub mnemonic_fg: u8,
+}
+
+#[derive(Clone, Copy)]
+pub struct DialogTheme {
+    pub backdrop: (u8, u8),  // recolors everything behind a dialog
 }
\ No newline at end of file
@@ -100,6 +100,7 @@ impl Theme {
             button: ButtonTheme { 
                 bevel: outset,
                 color: window,  // TODO: This is completely wrong!
+                focused: accent_subselected,
                 preclick: accent_preclick,
             },
             input_box: InputBoxTheme {
@@ -108,6 +109,38 @@ impl Theme {
                 selected: enclave,
                 preclick: accent_preclick,
                 cursor: accent_subselected,
+                invalid: (DkRed[1], enclave.1),
+            },
+            checkbox: ToggleTheme {
+                bevel: inset,
+                box_color: enclave,
+                focused: accent_subselected,
+                preclick: accent_preclick,
+            },
+            radio_group: ToggleTheme {
+                bevel: inset,
+                box_color: enclave,
+                focused: accent_subselected,
+                preclick: accent_preclick,
+            },
+            list: ListTheme {
+                bevel: inset,
+                color: enclave,
+                selected: accent_subselected,
+                cursor: (enclave.0, accent_subselected.0),
+                preclick: accent_preclick,
+            },
+            menu: MenuTheme {
+                bar: window,
+                bevel: outset,
+                color: window,
+                highlighted: accent_subselected,
+                preclick: accent_preclick,
+                disabled_fg: inset.1,
+                mnemonic_fg: accent_preclick.0,
+            },
+            dialog: DialogTheme {
+                backdrop: wallpaper,
             },
         }
     }
@@ -0,0 +1,211 @@
+use std::rc::Rc;
+
+use chiropterm::*;
+
+use crate::UI;
+
+use super::PopupMenu;
+
+// A key combination that triggers a menu item even while its menu is closed
+#[derive(Clone, Copy)]
+pub struct Accelerator {
+    pub key: Keycode,
+    pub control: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Accelerator {
+    pub fn new(key: Keycode) -> Accelerator {
+        Accelerator { key, control: false, shift: false, alt: false }
+    }
+
+    pub fn control(key: Keycode) -> Accelerator {
+        Accelerator { control: true, ..Accelerator::new(key) }
+    }
+
+    pub(crate) fn recognizer<'frame>(&self) -> KeyRecognizer<'frame> {
+        let mut k = OnKey::only(self.key);
+        if self.control { k = k.control() }
+        if self.shift { k = k.shift() }
+        if self.alt { k = k.alt() }
+        k.pressed()
+    }
+
+    pub(crate) fn label(&self) -> String {
+        let mut s = String::new();
+        if self.control { s.push_str("Ctrl+") }
+        if self.shift { s.push_str("Shift+") }
+        if self.alt { s.push_str("Alt+") }
+        s.push_str(&keycode_label(self.key));
+        s
+    }
+}
+
+pub struct MenuItem {
+    pub text: String,
+    pub mnemonic: Option<Keycode>,
+    pub accelerator: Option<Accelerator>,
+    pub enabled: bool,
+    pub checked: Option<bool>,  // None if the item isn't checkable
+    pub(crate) kind: MenuItemKind,
+}
+
+pub(crate) enum MenuItemKind {
+    // the bool is the item's checked state, after toggling it
+    Action(Rc<dyn Fn(UI, bool) -> Signal>),
+    Submenu(PopupMenu),
+    Separator,
+}
+
+impl MenuItem {
+    pub fn action(text: impl Into<String>, cmd: impl 'static+Fn(UI) -> Signal) -> MenuItem {
+        MenuItem::new(text.into(), MenuItemKind::Action(Rc::new(move |ui, _| cmd(ui))))
+    }
+
+    pub fn checkable(text: impl Into<String>, checked: bool, cmd: impl 'static+Fn(UI, bool) -> Signal) -> MenuItem {
+        let mut item = MenuItem::new(text.into(), MenuItemKind::Action(Rc::new(cmd)));
+        item.checked = Some(checked);
+        item
+    }
+
+    pub fn submenu(text: impl Into<String>, items: impl IntoIterator<Item=MenuItem>) -> MenuItem {
+        let popup = PopupMenu::new().setup(|p| {
+            for i in items { p.add_item(i) }
+        });
+        MenuItem::new(text.into(), MenuItemKind::Submenu(popup))
+    }
+
+    pub fn separator() -> MenuItem {
+        MenuItem::new(String::new(), MenuItemKind::Separator)
+    }
+
+    fn new(text: String, kind: MenuItemKind) -> MenuItem {
+        MenuItem {
+            text,
+            mnemonic: None,
+            accelerator: None,
+            enabled: true,
+            checked: None,
+            kind,
+        }
+    }
+
+    pub fn with_mnemonic(mut self, key: Keycode) -> MenuItem {
+        self.mnemonic = Some(key);
+        self
+    }
+
+    pub fn with_accelerator(mut self, accelerator: Accelerator) -> MenuItem {
+        self.accelerator = Some(accelerator);
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> MenuItem {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn is_separator(&self) -> bool {
+        if let MenuItemKind::Separator = self.kind { true } else { false }
+    }
+
+    pub fn submenu_items(&self) -> Option<PopupMenu> {
+        if let MenuItemKind::Submenu(p) = &self.kind { Some(p.share()) } else { None }
+    }
+
+    pub(crate) fn selectable(&self) -> bool {
+        self.enabled && !self.is_separator()
+    }
+}
+
+pub(crate) fn keycode_label(key: Keycode) -> String {
+    let label = match key {
+        Keycode::A => "A",
+        Keycode::B => "B",
+        Keycode::C => "C",
+        Keycode::D => "D",
+        Keycode::E => "E",
+        Keycode::F => "F",
+        Keycode::G => "G",
+        Keycode::H => "H",
+        Keycode::I => "I",
+        Keycode::J => "J",
+        Keycode::K => "K",
+        Keycode::L => "L",
+        Keycode::M => "M",
+        Keycode::N => "N",
+        Keycode::O => "O",
+        Keycode::P => "P",
+        Keycode::Q => "Q",
+        Keycode::R => "R",
+        Keycode::S => "S",
+        Keycode::T => "T",
+        Keycode::U => "U",
+        Keycode::V => "V",
+        Keycode::W => "W",
+        Keycode::X => "X",
+        Keycode::Y => "Y",
+        Keycode::Z => "Z",
+        Keycode::Key0 => "0",
+        Keycode::Key1 => "1",
+        Keycode::Key2 => "2",
+        Keycode::Key3 => "3",
+        Keycode::Key4 => "4",
+        Keycode::Key5 => "5",
+        Keycode::Key6 => "6",
+        Keycode::Key7 => "7",
+        Keycode::Key8 => "8",
+        Keycode::Key9 => "9",
+        Keycode::F1 => "F1",
+        Keycode::F2 => "F2",
+        Keycode::F3 => "F3",
+        Keycode::F4 => "F4",
+        Keycode::F5 => "F5",
+        Keycode::F6 => "F6",
+        Keycode::F7 => "F7",
+        Keycode::F8 => "F8",
+        Keycode::F9 => "F9",
+        Keycode::F10 => "F10",
+        Keycode::F11 => "F11",
+        Keycode::F12 => "F12",
+        Keycode::Escape => "Esc",
+        Keycode::Enter => "Enter",
+        Keycode::Tab => "Tab",
+        Keycode::Space => "Space",
+        Keycode::Backspace => "Backspace",
+        Keycode::Insert => "Ins",
+        Keycode::Delete => "Del",
+        Keycode::Home => "Home",
+        Keycode::End => "End",
+        Keycode::PageUp => "PgUp",
+        Keycode::PageDown => "PgDn",
+        Keycode::Up => "Up",
+        Keycode::Down => "Down",
+        Keycode::Left => "Left",
+        Keycode::Right => "Right",
+        Keycode::Minus => "-",
+        Keycode::Equal => "=",
+        Keycode::Comma => ",",
+        Keycode::Period => ".",
+        Keycode::Slash => "/",
+        Keycode::Backslash => "\\",
+        Keycode::Semicolon => ";",
+        Keycode::Apostrophe => "'",
+        Keycode::Backquote => "`",
+        Keycode::LeftBracket => "[",
+        Keycode::RightBracket => "]",
+        // nothing else makes a sensible shortcut, so it's fine to go by the variant name
+        _ => return format!("{:?}", key),
+    };
+    label.to_owned()
+}
+
+// Where in `text` to highlight the mnemonic, if anywhere
+pub(crate) fn mnemonic_position(text: &str, mnemonic: Option<Keycode>) -> Option<usize> {
+    let label = keycode_label(mnemonic?);
+    let mut chars = label.chars();
+    let c = chars.next()?;
+    if chars.next().is_some() { return None }
+    text.chars().position(|t| t.eq_ignore_ascii_case(&c))
+}
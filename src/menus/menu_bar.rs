@@ -0,0 +1,191 @@
+use std::cell::Cell;
+
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{InternalWidgetDimensions, OverlayOptions, UI, Widget, WidgetMenu, Widgetlike, widget::LayoutHacks};
+
+use super::{MenuItem, PopupMenu, item::mnemonic_position, popup::PopupStatus};
+
+pub type MenuBar = Widget<MenuBarState>;
+
+struct BarMenu {
+    title: String,
+    mnemonic: Option<Keycode>,
+    popup: PopupMenu,
+}
+
+pub struct MenuBarState {
+    menus: Vec<BarMenu>,
+    open: Cell<Option<usize>>,  // menus close themselves, so this gets fixed up during draw
+
+    pub layout_hacks: LayoutHacks,
+}
+
+impl MenuBarState {
+    // `mnemonic` opens the menu with Alt
+    pub fn add_menu(&mut self, title: impl Into<String>, mnemonic: Option<Keycode>, items: impl IntoIterator<Item=MenuItem>) -> PopupMenu {
+        let popup = PopupMenu::new().setup(|p| {
+            for i in items { p.add_item(i) }
+        });
+        self.menus.push(BarMenu { title: title.into(), mnemonic, popup: popup.share() });
+        popup
+    }
+
+    pub fn menu(&self, ix: usize) -> PopupMenu {
+        self.menus[ix].popup.share()
+    }
+
+    pub fn len(&self) -> usize {
+        self.menus.len()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.get().is_some()
+    }
+
+    fn open_menu(&self, ix: usize, highlight: bool) {
+        self.open.set(Some(ix));
+        let mut p = self.menus[ix].popup.borrow_mut();
+        p.unique.open();
+        if highlight { p.unique.highlight_first() }
+    }
+}
+
+impl Widgetlike for MenuBarState {
+    fn create() -> Self {
+        MenuBarState {
+            menus: vec![],
+            open: Cell::new(None),
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        // pick up whatever happened inside the open menu since the last frame
+        if let Some(i) = self.open.get() {
+            let status = self.menus[i].popup.borrow().unique.status();
+            match status {
+                PopupStatus::Open => {}
+                PopupStatus::Step(amount) => {
+                    let next = (i as isize + amount).rem_euclid(self.menus.len() as isize);
+                    self.open_menu(next as usize, true);
+                }
+                PopupStatus::Dismissed | PopupStatus::Chosen => self.open.set(None),
+            }
+        }
+
+        for m in self.menus.iter() {
+            register_accelerators(&menu, &m.popup);
+        }
+
+        let theme = menu.ui.theme().menu;
+        brush.fill(FSem::new().sem(SemanticContent::Blank).color(theme.bar));
+
+        let mut x = 0;
+        for (i, m) in self.menus.iter().enumerate() {
+            if let Some(k) = m.mnemonic {
+                menu.on_key(OnKey::only(k).alt().pressed(), move |_, this, _| {
+                    this.unique.open_menu(i, true);
+                    Signal::Refresh
+                });
+            }
+
+            let interactor = menu.on_mouse(move |_, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        if this.unique.open.get() == Some(i) {
+                            this.unique.open.set(None);
+                        } else {
+                            this.unique.open_menu(i, false);
+                        }
+                        return Signal::Refresh
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {
+                        // once one menu is open, hovering the others opens them instead
+                        match this.unique.open.get() {
+                            Some(o) if o != i => {
+                                this.unique.open_menu(i, false);
+                                return Signal::Refresh
+                            }
+                            _ => {}
+                        }
+                    }
+                };
+                Signal::Continue
+            });
+
+            let w = m.title.chars().count() as isize + 2;
+            let title = brush.region(rect(x, 0, w, 2)).interactor(interactor, theme.preclick);
+            if self.open.get() == Some(i) {
+                title.fill(FSem::new().sem(SemanticContent::Blank).color(theme.highlighted));
+            }
+            let text = title.region(rect(1, 0, w - 2, 2));
+            text.putfs(&m.title);
+            if let Some(ix) = mnemonic_position(&m.title, m.mnemonic) {
+                let c: String = m.title.chars().skip(ix).take(1).collect();
+                text.region(rect(ix as isize, 0, 1, 2)).fg(theme.mnemonic_fg).putfs(&c);
+            }
+
+            if self.open.get() == Some(i) {
+                let o = brush.cursor_offset();
+                let size = m.popup.borrow().unique.size();
+                let bar = menu.widget();
+                menu.ui.push_overlay_with(
+                    rect(o.x + x, o.y + 2, size.width, size.height), m.popup.share(),
+                    OverlayOptions::new().dismiss_on_outside_click(move |_| {
+                        bar.borrow().unique.open.set(None);
+                        Signal::Refresh
+                    }),
+                );
+            }
+            x += w;
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        let width = self.menus.iter().map(|m| m.title.chars().count() as isize + 2).sum();
+        InternalWidgetDimensions {
+            min: size2(0, 2),
+            preferred: size2(width, 2),
+            max: Some(size2(isize::MAX, 2)),
+            align_size_to: size2(1, 2),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+// Accelerators fire even while their menu is closed, so the bar binds them for every item in every menu
+fn register_accelerators<'frame>(menu: &WidgetMenu<'frame, MenuBarState>, popup: &PopupMenu) {
+    let p = popup.borrow();
+    for (i, item) in p.unique.items().iter().enumerate() {
+        if let Some(sub) = item.submenu_items() {
+            register_accelerators(menu, &sub);
+            continue
+        }
+        let accelerator = match item.accelerator {
+            Some(a) if item.enabled => a,
+            _ => continue
+        };
+
+        let popup = popup.share();
+        let ui = menu.ui.share();
+        menu.menu.on_key(accelerator.recognizer(), move |_| {
+            let triggered = popup.borrow_mut().unique.trigger(i);
+            match triggered {
+                Some((cmd, checked)) => cmd(ui.share(), checked),
+                None => Signal::Continue,
+            }
+        });
+    }
+}
@@ -0,0 +1,8 @@
+mod item;
+mod menu_bar;
+mod popup;
+
+pub use item::{Accelerator, MenuItem};
+pub use menu_bar::{MenuBar, MenuBarState};
+pub use popup::{PopupMenu, PopupMenuState};
+pub(crate) use popup::PopupStatus;
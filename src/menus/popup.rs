@@ -0,0 +1,347 @@
+use std::rc::Rc;
+
+use chiropterm::*;
+use euclid::{rect, size2};
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetMenu, Widgetlike, widget::LayoutHacks};
+
+use super::{MenuItem, item::{MenuItemKind, mnemonic_position}};
+
+pub type PopupMenu = Widget<PopupMenuState>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PopupStatus {
+    Open,
+    Dismissed,  // Escape, or Left from a submenu
+    Chosen,
+    Step(isize),  // Left/Right from a top-level menu: its owner should open a neighbour
+}
+
+// A list of menu items, normally drawn as an overlay by whatever opened it
+pub struct PopupMenuState {
+    items: Vec<MenuItem>,
+    highlighted: Option<usize>,
+    open_submenu: Option<usize>,
+    status: PopupStatus,
+    is_submenu: bool,
+
+    pub layout_hacks: LayoutHacks,
+}
+
+impl PopupMenuState {
+    pub fn add_item(&mut self, item: MenuItem) {
+        if let MenuItemKind::Submenu(p) = &item.kind {
+            p.borrow_mut().unique.is_submenu = true;
+        }
+        self.items.push(item)
+    }
+
+    pub fn items(&self) -> &[MenuItem] {
+        &self.items
+    }
+
+    pub fn item_mut(&mut self, ix: usize) -> &mut MenuItem {
+        &mut self.items[ix]
+    }
+
+    pub(crate) fn open(&mut self) {
+        self.status = PopupStatus::Open;
+        self.highlighted = None;
+        self.open_submenu = None;
+    }
+
+    pub(crate) fn status(&self) -> PopupStatus {
+        if self.status != PopupStatus::Open { return self.status }
+        if let Some(s) = self.submenu() {
+            if s.borrow().unique.status() == PopupStatus::Chosen { return PopupStatus::Chosen }
+        }
+        PopupStatus::Open
+    }
+
+    pub(crate) fn size(&self) -> CellSize {
+        let text_width = self.items.iter().map(|i| i.text.chars().count()).max().unwrap_or(0) as isize;
+        let height = self.items.iter().map(row_height).sum();
+        // check mark and submenu arrow columns on either side of the text
+        size2(3 + text_width + self.accelerator_width() + 3, height)
+    }
+
+    // Context menus have no neighbours to step to, so they just stay open
+    pub(crate) fn status_ignoring_steps(&mut self) -> PopupStatus {
+        if let PopupStatus::Step(_) = self.status { self.status = PopupStatus::Open }
+        self.status()
+    }
+
+    fn accelerator_width(&self) -> isize {
+        let w = self.items.iter()
+            .filter_map(|i| i.accelerator.map(|a| a.label().chars().count()))
+            .max();
+        match w {
+            Some(w) => w as isize + 2,
+            None => 0
+        }
+    }
+
+    fn submenu(&self) -> Option<&PopupMenu> {
+        let ix = self.open_submenu?;
+        match &self.items[ix].kind {
+            MenuItemKind::Submenu(p) => Some(p),
+            _ => None
+        }
+    }
+
+    fn active_submenu(&self) -> Option<&PopupMenu> {
+        self.submenu().filter(|s| s.borrow().unique.status() == PopupStatus::Open)
+    }
+
+    pub(crate) fn highlight_first(&mut self) {
+        self.highlighted = None;
+        self.move_highlight(1);
+    }
+
+    fn move_highlight(&mut self, amount: isize) {
+        let n = self.items.len() as isize;
+        if n == 0 { return }
+
+        let mut h = match self.highlighted {
+            Some(h) => h as isize,
+            None if amount > 0 => -1,
+            None => n,
+        };
+        for _ in 0..n {
+            h = (h + amount.signum()).rem_euclid(n);
+            if self.items[h as usize].selectable() {
+                self.highlighted = Some(h as usize);
+                return
+            }
+        }
+    }
+
+    // Toggles the item if it's checkable and closes the menu if it's open, returning the command to run.
+    // Callers should release their borrow on the menu before running it
+    pub(crate) fn trigger(&mut self, ix: usize) -> Option<(Rc<dyn Fn(UI, bool) -> Signal>, bool)> {
+        let item = &mut self.items[ix];
+        if !item.selectable() { return None }
+        let cmd = match &item.kind {
+            MenuItemKind::Action(cmd) => cmd.clone(),
+            _ => return None
+        };
+        if let Some(c) = item.checked.as_mut() { *c = !*c }
+        let checked = item.checked.unwrap_or(false);
+        // an accelerator can fire while the menu is closed, and then there's nothing to close
+        if self.status == PopupStatus::Open { self.status = PopupStatus::Chosen }
+        Some((cmd, checked))
+    }
+
+    fn activate(&mut self, ix: usize) -> Activation {
+        if !self.items[ix].selectable() { return Activation::Ignored }
+        self.highlighted = Some(ix);
+
+        if let Some(sub) = self.items[ix].submenu_items() {
+            let mut s = sub.borrow_mut();
+            s.unique.open();
+            s.unique.highlight_first();
+            self.open_submenu = Some(ix);
+            return Activation::OpenedSubmenu
+        }
+
+        match self.trigger(ix) {
+            Some((cmd, checked)) => Activation::Chose(cmd, checked),
+            None => Activation::Ignored,
+        }
+    }
+
+    fn hover(&mut self, ix: usize) {
+        if self.highlighted == Some(ix) { return }
+        self.highlighted = Some(ix);
+        self.open_submenu = None;
+
+        if !self.items[ix].enabled { return }
+        if let Some(sub) = self.items[ix].submenu_items() {
+            sub.borrow_mut().unique.open();
+            self.open_submenu = Some(ix);
+        }
+    }
+}
+
+impl Widgetlike for PopupMenuState {
+    fn create() -> Self {
+        PopupMenuState {
+            items: vec![],
+            highlighted: None,
+            open_submenu: None,
+            status: PopupStatus::Dismissed,
+            is_submenu: false,
+
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        let submenu = self.active_submenu();
+
+        // only the innermost open menu takes keys
+        if submenu.is_none() {
+            for (key, amount) in [(Keycode::Up, -1), (Keycode::Down, 1)] {
+                menu.on_key_hprio(OnKey::only(key).pressed(), move |_, this, _| {
+                    this.unique.open_submenu = None;
+                    this.unique.move_highlight(amount);
+                    Signal::Refresh
+                });
+            }
+            for key in [Keycode::Enter, Keycode::Space] {
+                bind_activate(&menu, OnKey::only(key).pressed(), |p| p.highlighted);
+            }
+            menu.on_key_hprio(OnKey::only(Keycode::Right).pressed(), |_, this, _| {
+                if let Some(h) = this.unique.highlighted {
+                    // opening a submenu doesn't run anything, so it's fine to do with the menu borrowed
+                    if this.unique.items[h].submenu_items().is_some() {
+                        this.unique.activate(h);
+                        return Signal::Refresh
+                    }
+                }
+                if this.unique.is_submenu { return Signal::Continue }
+                this.unique.status = PopupStatus::Step(1);
+                Signal::Refresh
+            });
+            menu.on_key_hprio(OnKey::only(Keycode::Left).pressed(), |_, this, _| {
+                this.unique.status = if this.unique.is_submenu { 
+                    PopupStatus::Dismissed 
+                } else { 
+                    PopupStatus::Step(-1) 
+                };
+                Signal::Refresh
+            });
+            menu.on_key_hprio(OnKey::only(Keycode::Escape).pressed(), |_, this, _| {
+                this.unique.status = PopupStatus::Dismissed;
+                Signal::Refresh
+            });
+
+            for (i, item) in self.items.iter().enumerate() {
+                if let Some(m) = item.mnemonic {
+                    bind_activate(&menu, OnKey::only(m).pressed(), move |_| Some(i));
+                }
+            }
+        }
+
+        let theme = menu.ui.theme().menu;
+        brush.fill(FSem::new().sem(SemanticContent::Blank).color(theme.color));
+        brush.bevel_w95(theme.bevel);
+
+        let width = brush.rect().width();
+        let accelerator_width = self.accelerator_width();
+        let mut y = 0;
+        let mut submenu_y = 0;
+        for (i, item) in self.items.iter().enumerate() {
+            let h = row_height(item);
+            let row = brush.region(rect(0, y, width, h));
+            if self.open_submenu == Some(i) { submenu_y = y; }
+            y += h;
+
+            if item.is_separator() {
+                row.region(rect(1, 0, width - 2, h)).fill(FSem::new().sem(SemanticContent::Small(0xc4)).fg(theme.color.1));
+                continue
+            }
+
+            // not menu.on_mouse: that would hold the menu borrowed while the item's command runs
+            let popup = menu.widget();
+            let ui = menu.ui.share();
+            let interactor = menu.menu.on_mouse(move |click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        return activate(ui.share(), &popup, i)
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {
+                        popup.borrow_mut().unique.hover(i);
+                        return Signal::Refresh
+                    }
+                };
+                Signal::Continue
+            });
+
+            let color = if self.highlighted == Some(i) && item.enabled { theme.highlighted } else { theme.color };
+            let row = row.interactor(interactor, theme.preclick);
+            row.fill(FSem::new().sem(SemanticContent::Blank).color(color));
+            let row = if item.enabled { row } else { row.fg(theme.disabled_fg) };
+
+            if item.checked == Some(true) {
+                row.region(rect(1, 0, 2, 2)).font(Font::Set).putch(0xfbu16);  // CP437 check mark
+            }
+
+            let text = row.region(rect(3, 0, width - 6, 2));
+            text.putfs(&item.text);
+            if let (true, Some(ix)) = (item.enabled, mnemonic_position(&item.text, item.mnemonic)) {
+                let c: String = item.text.chars().skip(ix).take(1).collect();
+                text.region(rect(ix as isize, 0, 1, 2)).fg(theme.mnemonic_fg).putfs(&c);
+            }
+
+            if let Some(a) = item.accelerator {
+                let label = a.label();
+                let x = width - 3 - (accelerator_width - 2);
+                row.region(rect(x, 0, label.chars().count() as isize, 2)).putfs(&label);
+            }
+
+            if item.submenu_items().is_some() {
+                row.region(rect(width - 3, 0, 2, 2)).font(Font::Set).putch(0x10u16);  // CP437 right-pointing triangle
+            }
+        }
+
+        if let Some(sub) = submenu {
+            let o = brush.cursor_offset();
+            let size = sub.borrow().unique.size();
+            menu.ui.push_overlay(rect(o.x + width, o.y + submenu_y, size.width, size.height), sub.share());
+        }
+    }
+
+    fn estimate_dimensions(&self, _: &UI, _width: isize) -> InternalWidgetDimensions {
+        let size = self.size();
+        InternalWidgetDimensions {
+            min: size,
+            preferred: size,
+            max: Some(size),
+            align_size_to: size2(1, 1),
+            horizontal_spacer_count: 0,
+            vertical_spacer_count: 0,
+        }
+    }
+
+    fn clear_layout_cache(&self, _: &UI) { }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+// What activating an item did. A chosen item's command still needs running
+enum Activation {
+    Ignored,
+    OpenedSubmenu,
+    Chose(Rc<dyn Fn(UI, bool) -> Signal>, bool),
+}
+
+// Activates item `ix`, running its command only once the borrow on the menu has been released
+fn activate(ui: UI, popup: &PopupMenu, ix: usize) -> Signal {
+    let activation = popup.borrow_mut().unique.activate(ix);
+    match activation {
+        Activation::Ignored => Signal::Continue,
+        Activation::OpenedSubmenu => Signal::Refresh,
+        Activation::Chose(cmd, checked) => cmd(ui, checked),
+    }
+}
+
+fn bind_activate<'frame>(menu: &WidgetMenu<'frame, PopupMenuState>, key: KeyRecognizer<'frame>, pick: impl 'frame+Fn(&PopupMenuState) -> Option<usize>) {
+    let popup = menu.widget();
+    let ui = menu.ui.share();
+    menu.menu.on_key_hprio(key, move |_| {
+        let ix = pick(&popup.borrow().unique);
+        match ix {
+            Some(ix) => activate(ui.share(), &popup, ix),
+            None => Signal::Continue,
+        }
+    });
+}
+
+fn row_height(item: &MenuItem) -> isize {
+    if item.is_separator() { 1 } else { 2 }
+}
@@ -0,0 +1,32 @@
+use std::cell::RefCell;
+
+// Where copied text goes. Implement this to hook up the system clipboard, then pass it to UI::set_clipboard
+pub trait Clipboard {
+    fn get_text(&self) -> String;
+    fn set_text(&self, text: &str);
+}
+
+// The default: only visible inside this process
+pub struct LocalClipboard {
+    text: RefCell<String>,
+}
+
+impl LocalClipboard {
+    pub fn new() -> LocalClipboard {
+        LocalClipboard { text: RefCell::new(String::new()) }
+    }
+}
+
+impl Default for LocalClipboard {
+    fn default() -> LocalClipboard { LocalClipboard::new() }
+}
+
+impl Clipboard for LocalClipboard {
+    fn get_text(&self) -> String {
+        self.text.borrow().clone()
+    }
+
+    fn set_text(&self, text: &str) {
+        self.text.replace(text.to_owned());
+    }
+}
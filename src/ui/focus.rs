@@ -0,0 +1,99 @@
+use std::rc::Rc;
+
+use smallvec::SmallVec;
+
+use super::{Selection, UI};
+
+// Smallvec size -- set this to "deeper than most users will ever nest focus scopes"
+const SM: usize = 4;
+
+// Rebuilt every time the root widget is drawn: focusable widgets register themselves in tree order
+pub(crate) struct FocusRing {
+    entries: Vec<FocusEntry>,
+    scope_stack: SmallVec<[usize; SM]>,
+    n_scopes: usize,
+    claimed: bool,  // see claim
+}
+
+struct FocusEntry {
+    selection: Selection,
+    scopes: SmallVec<[usize; SM]>,
+    focus: Rc<dyn Fn(&UI)>,
+}
+
+impl FocusRing {
+    pub(crate) fn new() -> FocusRing {
+        FocusRing {
+            entries: Vec::new(),
+            scope_stack: SmallVec::new(),
+            n_scopes: 0,
+            claimed: false,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.scope_stack.clear();
+        self.n_scopes = 0;
+        self.claimed = false;
+    }
+
+    pub(crate) fn register(&mut self, selection: Selection, focus: Rc<dyn Fn(&UI)>) {
+        self.entries.push(FocusEntry {
+            selection,
+            scopes: self.scope_stack.clone(),
+            focus
+        })
+    }
+
+    pub(crate) fn begin_scope(&mut self) {
+        self.n_scopes += 1;
+        self.scope_stack.push(self.n_scopes);
+    }
+
+    pub(crate) fn end_scope(&mut self) {
+        self.scope_stack.pop();
+    }
+
+    pub(crate) fn mark(&self) -> usize {
+        self.entries.len()
+    }
+
+    // True if the current widget registered after `mark`, for the first caller to ask.
+    // Children finish drawing before their parents, so that's the innermost widget around the focus
+    pub(crate) fn claim(&mut self, mark: usize, current: Selection) -> bool {
+        if self.claimed { return false }
+        self.claimed = self.entries[mark..].iter().any(|e| e.selection == current);
+        self.claimed
+    }
+
+    // Returns the callback that focuses the widget after (or before) the current one.
+    // Traversal never leaves the innermost scope containing the current widget.
+    pub(crate) fn step(&self, current: Selection, forward: bool) -> Option<Rc<dyn Fn(&UI)>> {
+        if self.entries.len() == 0 { return None }
+
+        let current_ix = match self.entries.iter().position(|e| e.selection == current) {
+            Some(ix) => ix,
+            None => {
+                // nothing in this tree is focused: start from the appropriate edge
+                let e = if forward { self.entries.first() } else { self.entries.last() };
+                return e.map(|e| e.focus.clone())
+            }
+        };
+
+        let scope = self.entries[current_ix].scopes.last().cloned();
+        let in_scope = |e: &FocusEntry| match scope {
+            Some(s) => e.scopes.contains(&s),
+            None => true,
+        };
+
+        let n = self.entries.len();
+        for i in 1..=n {
+            let ix = if forward { (current_ix + i) % n } else { (current_ix + n - i) % n };
+            if in_scope(&self.entries[ix]) {
+                return Some(self.entries[ix].focus.clone())
+            }
+        }
+        None
+    }
+}
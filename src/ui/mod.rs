@@ -1,15 +1,34 @@
+mod clipboard;
+mod focus;
+mod overlay;
 mod selection;
 
-use std::{cell::Cell, rc::Rc};
+use std::{cell::{Cell, RefCell}, rc::{Rc, Weak}};
 
+use chiropterm::{Brush, CellPoint, CellRect, FSem, Keycode, Menu, MouseEvent, OnKey, Signal};
+use euclid::rect;
+
+use self::{focus::FocusRing, overlay::Overlay};
+pub use self::clipboard::{Clipboard, LocalClipboard};
+pub use self::overlay::OverlayOptions;
 pub use self::selection::Selection;
 
-use super::{Theme, WidgetCommon, Widgetlike};
+use super::{AnyWidget, PopupMenu, Theme, WidgetCommon, Widgetlike, menus::PopupStatus};
 
 pub struct UISource {
     selection: Cell<Selection>,
     layout_token: Cell<u64>,
     theme: Cell<Theme>,
+
+    draw_depth: Cell<usize>,
+    focus: RefCell<FocusRing>,
+    overlays: RefCell<Vec<Overlay>>,
+    overlay_z: Cell<isize>,
+    context_menu: RefCell<Option<(CellPoint, PopupMenu)>>,
+    clipboard: RefCell<Rc<dyn Clipboard>>,
+    last_root: RefCell<Option<(AnyWidget, CellRect)>>,
+    modifiers: Cell<(bool, bool)>,  // shift, control: see track_modifiers
+    layout_parent: Option<Rc<UISource>>,  // whose layout this UI's tree really belongs to: see backdrop
 }
 
 #[derive(Clone)]
@@ -20,11 +39,31 @@ pub struct UI {
 
 impl UI {
     pub fn new(theme: Theme) -> UI {
+        UI::create(theme, None)
+    }
+
+    // A UI for drawing this one's tree where it can't take input or focus, like behind a dialog.
+    // The tree still lays out (and asks for relayouts) as part of this UI
+    pub(crate) fn backdrop(&self) -> UI {
+        UI::create(self.theme(), Some(self.layout_state().clone())).with_context(|c| c.active = false)
+    }
+
+    fn create(theme: Theme, layout_parent: Option<Rc<UISource>>) -> UI {
         UI {
             state: Rc::new(UISource { 
                 selection: Cell::new(Selection::none()),
                 layout_token: Cell::new(0),
                 theme: Cell::new(theme),
+
+                draw_depth: Cell::new(0),
+                focus: RefCell::new(FocusRing::new()),
+                overlays: RefCell::new(vec![]),
+                overlay_z: Cell::new(isize::MIN),
+                context_menu: RefCell::new(None),
+                clipboard: RefCell::new(Rc::new(LocalClipboard::new())),
+                last_root: RefCell::new(None),
+                modifiers: Cell::new((false, false)),
+                layout_parent,
             }),
             context: UIContext::new(),
         }
@@ -51,12 +90,210 @@ impl UI {
         self.state.selection.get() == other
     }
 
+    pub fn focus_next(&self) {
+        let next = self.state.focus.borrow().step(self.state.selection.get(), true);
+        if let Some(n) = next { n(self) }
+    }
+
+    pub fn focus_previous(&self) {
+        let prev = self.state.focus.borrow().step(self.state.selection.get(), false);
+        if let Some(p) = prev { p(self) }
+    }
+
+    pub(crate) fn register_focus(&self, selection: Selection, focus: Rc<dyn Fn(&UI)>) {
+        self.state.focus.borrow_mut().register(selection, focus)
+    }
+
+    pub(crate) fn begin_focus_scope(&self) {
+        self.state.focus.borrow_mut().begin_scope()
+    }
+
+    pub(crate) fn end_focus_scope(&self) {
+        self.state.focus.borrow_mut().end_scope()
+    }
+
+    // Take one of these before drawing children, then pass it to claim_focus afterwards
+    pub(crate) fn focus_mark(&self) -> usize {
+        self.state.focus.borrow().mark()
+    }
+
+    // True if the focused widget was drawn since `mark` and nothing drawn in between claimed it first.
+    // For keys that should only work near the focus, and only for the innermost widget that wants them
+    pub(crate) fn claim_focus(&self, mark: usize) -> bool {
+        self.state.focus.borrow_mut().claim(mark, self.state.selection.get())
+    }
+
+    // Floating content, drawn over the whole tree once the current root finishes drawing.
+    // `at` is in screen coordinates: see Brush::cursor_offset
+    pub fn push_overlay<X: Into<AnyWidget>>(&self, at: CellRect, widget: X) {
+        self.push_overlay_with(at, widget, OverlayOptions::new())
+    }
+
+    pub fn push_overlay_with<X: Into<AnyWidget>>(&self, at: CellRect, widget: X, mut options: OverlayOptions) {
+        // overlays opened by other overlays (think nested menus) always go on top of them
+        options.z = options.z.max(self.state.overlay_z.get());
+        self.state.overlays.borrow_mut().push(Overlay { at, widget: widget.into(), options })
+    }
+
+    // `at` is in screen coordinates. Replaces any context menu that's already open
+    pub fn open_context_menu(&self, at: CellPoint, popup: PopupMenu) {
+        popup.borrow_mut().unique.open();
+        self.state.context_menu.replace(Some((at, popup)));
+    }
+
+    pub fn close_context_menu(&self) {
+        self.state.context_menu.replace(None);
+    }
+
+    // Replaces the in-process clipboard, ex. with one backed by the host's system clipboard
+    pub fn set_clipboard(&self, clipboard: impl 'static+Clipboard) {
+        self.state.clipboard.replace(Rc::new(clipboard));
+    }
+
+    pub fn clipboard_text(&self) -> String {
+        let clipboard = self.state.clipboard.borrow().clone();
+        clipboard.get_text()
+    }
+
+    pub fn set_clipboard_text(&self, text: impl AsRef<str>) {
+        let clipboard = self.state.clipboard.borrow().clone();
+        clipboard.set_text(text.as_ref())
+    }
+
+    // The tree most recently drawn from outside any widget, and where on the screen it went
+    pub(crate) fn last_root(&self) -> Option<(AnyWidget, CellRect)> {
+        self.state.last_root.borrow().as_ref().map(|(w, r)| (w.share(), *r))
+    }
+
+    pub(crate) fn set_last_root(&self, widget: AnyWidget, at: CellRect) {
+        self.state.last_root.replace(Some((widget, at)));
+    }
+
+    // Called around every widget draw: the outermost call is the root of the tree. Returns true for the root
+    pub(crate) fn begin_draw(&self) -> bool {
+        let is_root = self.state.draw_depth.get() == 0;
+        if is_root {
+            self.state.focus.borrow_mut().clear();
+            self.state.overlays.borrow_mut().clear();
+        }
+        self.state.draw_depth.replace(self.state.draw_depth.get() + 1);
+        is_root
+    }
+
+    pub(crate) fn end_draw<'a, 'frame>(&self, root: Option<(Brush<'a>, Menu<'frame>)>) {
+        let (brush, menu) = match root {
+            Some(r) => r,
+            None => {
+                self.state.draw_depth.replace(self.state.draw_depth.get() - 1);
+                return
+            }
+        };
+
+        let context_menu = self.state.context_menu.borrow().as_ref().map(|(at, p)| (*at, p.share()));
+        if let Some((at, popup)) = context_menu {
+            let status = popup.borrow_mut().unique.status_ignoring_steps();
+            if status == PopupStatus::Open {
+                let size = popup.borrow().unique.size();
+                self.push_overlay_with(
+                    rect(at.x, at.y, size.width, size.height), popup,
+                    OverlayOptions::new().z(isize::MAX).dismiss_on_outside_click(|ui| {
+                        ui.close_context_menu();
+                        Signal::Refresh
+                    }),
+                );
+            } else {
+                self.state.context_menu.replace(None);
+            }
+        }
+
+        // cover the tree with an interactor that catches clicks missing every overlay
+        let dismissals: Vec<_> = self.state.overlays.borrow().iter()
+            .filter_map(|o| o.options.on_outside_click.clone())
+            .collect();
+        if dismissals.len() > 0 {
+            let ui = self.share();
+            let catcher = menu.on_mouse(move |click| {
+                match click {
+                    MouseEvent::Click(_, _, _) => {
+                        for d in dismissals.iter() { d(ui.share()); }
+                        return Signal::Refresh
+                    }
+                    MouseEvent::Up(_, _, _) => {}
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+            brush.interactor(catcher, self.theme().base.wallpaper).fill(FSem::new());
+        }
+
+        // overlays can open more overlays (think nested menus), so keep going until none are left
+        loop {
+            let mut overlays = self.state.overlays.replace(vec![]);
+            if overlays.len() == 0 { break }
+            overlays.sort_by_key(|o| o.options.z);  // stable: same-z overlays keep the order they were pushed in
+            for o in overlays.iter() {
+                self.state.overlay_z.replace(o.options.z);
+                o.draw(self, &brush, menu.share());
+            }
+        }
+        self.state.overlay_z.replace(isize::MIN);
+        self.state.draw_depth.replace(self.state.draw_depth.get() - 1);
+
+        // the root handles focus traversal, with lower priority than anything in the tree
+        let ui = self.share();
+        menu.on_key(OnKey::only(Keycode::Tab).pressed(), move |_| {
+            ui.focus_next();
+            Signal::Refresh
+        });
+        let ui = self.share();
+        menu.on_key(OnKey::only(Keycode::Tab).shift().pressed(), move |_| {
+            ui.focus_previous();
+            Signal::Refresh
+        });
+        self.track_modifiers(&menu);
+    }
+
+    // Whether shift and control are held down. For clicks, which don't say
+    pub(crate) fn modifiers(&self) -> (bool, bool) {
+        self.state.modifiers.get()
+    }
+
+    // Mouse events don't carry the modifier keys, so follow them from the keyboard instead.
+    // The recognizers match modifiers exactly, so every combination of the other one needs its own
+    fn track_modifiers<'frame>(&self, menu: &Menu<'frame>) {
+        let keys = [(Keycode::LeftShift, true), (Keycode::RightShift, true), (Keycode::LeftCtrl, false), (Keycode::RightCtrl, false)];
+        for (key, is_shift) in keys {
+            for (shift, control) in [(false, false), (true, false), (false, true), (true, true)] {
+                for down in [true, false] {
+                    let mut k = OnKey::only(key);
+                    if shift { k = k.shift() }
+                    if control { k = k.control() }
+                    let k = if down { k.pressed() } else { k.released() };
+
+                    let ui = self.share();
+                    menu.on_key(k, move |_| {
+                        let (shift_down, control_down) = ui.state.modifiers.get();
+                        ui.state.modifiers.replace(if is_shift { (down, control_down) } else { (shift_down, down) });
+                        Signal::Continue
+                    });
+                }
+            }
+        }
+    }
+
     pub fn recompute_layout(&self) {
-        self.state.layout_token.replace(self.state.layout_token.get() + 1);
+        let state = self.layout_state();
+        state.layout_token.replace(state.layout_token.get() + 1);
     }
 
     pub(in crate) fn layout_token(&self) -> u64 {
-        self.state.layout_token.get()
+        self.layout_state().layout_token.get()
+    }
+
+    fn layout_state(&self) -> &Rc<UISource> {
+        self.state.layout_parent.as_ref().unwrap_or(&self.state)
     }
 
     pub fn with_context(mut self, on_ctx: impl FnOnce(&mut UIContext)) -> UI {
@@ -69,6 +306,28 @@ impl UI {
     }
 }
 
+// Remembers the UI a widget was last drawn by, so the widget can ask for a relayout of that tree without one being passed in
+pub(crate) struct LayoutOwner {
+    ui: RefCell<Weak<UISource>>,
+}
+
+impl LayoutOwner {
+    pub(crate) fn new() -> LayoutOwner {
+        LayoutOwner { ui: RefCell::new(Weak::new()) }
+    }
+
+    pub(crate) fn attach(&self, ui: &UI) {
+        self.ui.replace(Rc::downgrade(ui.layout_state()));
+    }
+
+    // Not drawn yet: nothing has laid it out, so there's nothing to redo
+    pub(crate) fn recompute_layout(&self) {
+        if let Some(state) = self.ui.borrow().upgrade() {
+            state.layout_token.replace(state.layout_token.get() + 1);
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct UIContext {
     pub active: bool,
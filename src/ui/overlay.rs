@@ -0,0 +1,51 @@
+use std::rc::Rc;
+
+use chiropterm::{Brush, CellRect, Menu, Signal};
+
+use crate::AnyWidget;
+
+use super::UI;
+
+// Floating content: drawn by the root after the rest of the tree, so it isn't clipped by any parent
+pub(crate) struct Overlay {
+    pub(crate) at: CellRect,  // screen coordinates
+    pub(crate) widget: AnyWidget,
+    pub(crate) options: OverlayOptions,
+}
+
+#[derive(Clone)]
+pub struct OverlayOptions {
+    // higher overlays are drawn later, so they paint over lower ones and their interactors win
+    pub z: isize,
+    // if set, clicking anywhere but an overlay runs this instead of reaching the widget under the mouse
+    pub on_outside_click: Option<Rc<dyn Fn(UI) -> Signal>>,
+}
+
+impl OverlayOptions {
+    pub fn new() -> OverlayOptions {
+        OverlayOptions { z: 0, on_outside_click: None }
+    }
+
+    pub fn z(mut self, z: isize) -> OverlayOptions {
+        self.z = z;
+        self
+    }
+
+    pub fn dismiss_on_outside_click(mut self, on_dismiss: impl 'static+Fn(UI) -> Signal) -> OverlayOptions {
+        self.on_outside_click = Some(Rc::new(on_dismiss));
+        self
+    }
+}
+
+impl Overlay {
+    pub(crate) fn draw<'a, 'frame>(&self, ui: &UI, root: &Brush<'a>, menu: Menu<'frame>) {
+        let mut at = self.at.translate(-root.cursor_offset());
+
+        // keep it on the screen, preferring to show its top-left corner
+        let bounds = root.rect();
+        at.origin.x = at.origin.x.min(bounds.max_x() - at.size.width).max(bounds.min_x());
+        at.origin.y = at.origin.y.min(bounds.max_y() - at.size.height).max(bounds.min_y());
+
+        self.widget.draw_root(ui.share(), root.region(at), menu)
+    }
+}
@@ -2,7 +2,7 @@ use std::cell::Cell;
 
 use chiropterm::{Brush};
 
-use crate::{UI, ui::Selection};
+use crate::{PopupMenu, UI, ui::Selection};
 
 use super::{WidgetDimensions, InternalWidgetDimensions, WidgetMenu, Widgetlike};
 
@@ -10,6 +10,7 @@ pub struct WidgetCommon<T> {
     pub unique: T,
     pub(in crate) selection: Selection,
     pub(in crate) layout_token: Cell<u64>,
+    pub(in crate) context_menu: Option<PopupMenu>,
 
     last_dimensions: Cell<(isize, InternalWidgetDimensions)>,
 }
@@ -22,6 +23,7 @@ impl<T: Widgetlike> WidgetCommon<T> {
             selection: Selection::not_selected(),
             last_dimensions: Cell::new((-1, InternalWidgetDimensions::zero())),
             layout_token: Cell::new(0),
+            context_menu: None,
         }
     }
 
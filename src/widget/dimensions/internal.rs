@@ -1,6 +1,8 @@
 use chiropterm::*;
 use euclid::{rect, size2};
 
+use crate::LayoutHacks;
+
 use super::WidgetDimensions;
 
 // TODO: "InternalWidgetDimensions" with an optional max and align
@@ -34,7 +36,14 @@ impl InternalWidgetDimensions {
         self
     }
 
-    pub fn tailor<'a>(&self, brush: Brush<'a>) -> Brush<'a> {
+    pub fn tailor<'a>(&self, brush: Brush<'a>, hacks: LayoutHacks) -> Brush<'a> {
+        let size = brush.rect().size;
+        let brush = brush.region(hacks.place(
+            (size.width, size.height), 
+            (self.preferred.width, self.preferred.height), 
+            self.align_size_to.height,
+        ));
+
         let existing_size = brush.rect().size;
 
         let region = brush.region(rect(
@@ -1,3 +1,6 @@
+use chiropterm::CellRect;
+use euclid::rect;
+
 use super::WidgetDimensions;
 
 #[derive(Clone, Copy)]
@@ -8,6 +11,32 @@ pub struct LayoutHacks {
     // NYEO NOTE: It's completely OK for these to be settable to arbitrary values because the min/max constraints will be handled in tailor()
     pub preferred_width: Option<usize>,
     pub preferred_height: Option<usize>,
+    // these include the padding, but not the margin
+    pub max_width: Option<usize>,
+    pub max_height: Option<usize>,
+
+    // margin is outside the widget. padding is inside it, so it moves with the widget when it's aligned
+    pub margin: Sides,
+    pub padding: Sides,
+    // where to put the widget when its plot is bigger than it wants
+    pub horizontal_align: Align,
+    pub vertical_align: Align,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sides {
+    pub top: isize,
+    pub right: isize,
+    pub bottom: isize,
+    pub left: isize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    Stretch,
 }
 
 impl LayoutHacks {
@@ -18,6 +47,13 @@ impl LayoutHacks {
 
             preferred_width: None,
             preferred_height: None,
+            max_width: None,
+            max_height: None,
+
+            margin: Sides::zero(),
+            padding: Sides::zero(),
+            horizontal_align: Align::Stretch,
+            vertical_align: Align::Stretch,
         }
     }
 
@@ -36,6 +72,85 @@ impl LayoutHacks {
             wd.preferred.height = h as isize;
             wd.min.height = wd.min.height.min(h as isize);
         }
+        if let Some(w) = self.max_width {
+            let w = (w as isize - self.padding.horizontal()).max(0);
+            wd.preferred.width = wd.preferred.width.min(w);
+            wd.min.width = wd.min.width.min(w);
+        }
+        if let Some(h) = self.max_height {
+            let h = (h as isize - self.padding.vertical()).max(0);
+            wd.preferred.height = wd.preferred.height.min(h);
+            wd.min.height = wd.min.height.min(h);
+        }
+
+        let extra_w = self.margin.horizontal() + self.padding.horizontal();
+        let extra_h = self.margin.vertical() + self.padding.vertical();
+        wd.min.width += extra_w;
+        wd.min.height += extra_h;
+        wd.preferred.width += extra_w;
+        wd.preferred.height += extra_h;
         wd
     }
-}
\ No newline at end of file
+
+    // Space taken up by the margin and padding, which the widget never sees
+    pub(crate) fn extra_width(&self) -> isize {
+        self.margin.horizontal() + self.padding.horizontal()
+    }
+
+    // Where the widget goes inside a plot of `size`, given what it wants. Relative to the plot
+    pub(crate) fn place(&self, size: (isize, isize), preferred: (isize, isize), align_y: isize) -> CellRect {
+        let preferred = (
+            self.preferred_width.map(|w| w as isize).unwrap_or(preferred.0),
+            self.preferred_height.map(|h| h as isize).unwrap_or(preferred.1),
+        );
+        let (x, w) = place_1d(
+            size.0 - self.margin.horizontal(), preferred.0 + self.padding.horizontal(), 
+            self.horizontal_align, self.max_width, 1,
+        );
+        let (y, h) = place_1d(
+            size.1 - self.margin.vertical(), preferred.1 + self.padding.vertical(), 
+            self.vertical_align, self.max_height, align_y,
+        );
+        rect(
+            self.margin.left + x + self.padding.left, 
+            self.margin.top + y + self.padding.top, 
+            (w - self.padding.horizontal()).max(0), 
+            (h - self.padding.vertical()).max(0),
+        )
+    }
+}
+
+fn place_1d(available: isize, preferred: isize, align: Align, max: Option<usize>, align_to: isize) -> (isize, isize) {
+    let available = available.max(0);
+    let mut size = match align {
+        Align::Stretch => available,
+        _ => preferred.min(available),
+    };
+    if let Some(m) = max { size = size.min(m as isize) }
+
+    let mut offset = match align {
+        Align::Start | Align::Stretch => 0,
+        Align::Center => (available - size) / 2,
+        Align::End => available - size,
+    };
+    // don't knock two-cell-high text off its usual grid
+    if align_to > 1 { offset -= offset % align_to; }
+    (offset, size)
+}
+
+impl Sides {
+    pub const fn zero() -> Sides {
+        Sides { top: 0, right: 0, bottom: 0, left: 0 }
+    }
+
+    pub const fn all(amount: isize) -> Sides {
+        Sides { top: amount, right: amount, bottom: amount, left: amount }
+    }
+
+    pub const fn new(top: isize, right: isize, bottom: isize, left: isize) -> Sides {
+        Sides { top, right, bottom, left }
+    }
+
+    pub fn horizontal(&self) -> isize { self.left + self.right }
+    pub fn vertical(&self) -> isize { self.top + self.bottom }
+}
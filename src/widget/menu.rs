@@ -4,7 +4,7 @@ use chiropterm::*;
 
 use crate::ui::{UI, UIContext};
 
-use super::{Widgetlike, common::WidgetCommon};
+use super::{Widget, Widgetlike, common::WidgetCommon};
 
 pub struct WidgetMenu<'frame, T: Widgetlike> {
     pub ui: UI,
@@ -44,6 +44,13 @@ impl<'frame, T: Widgetlike> WidgetMenu<'frame, T> {
         let o = self.brush_offset;
         let ui = self.ui.share();
         self.menu.on_mouse(move |inp| {
+            if let MouseEvent::Click(MouseButton::Right, at, _) = inp {
+                // inp hasn't been offset yet, so `at` is in screen coordinates
+                if let Some(popup) = (Widget { state: state.clone() }).context_menu() {
+                    ui.open_context_menu(at, popup);
+                    return Signal::Refresh
+                }
+            }
             cb(ui.share(), &mut state.borrow_mut(), inp.offset(-o))
         })
     }
@@ -64,6 +71,18 @@ impl<'frame, T: Widgetlike> WidgetMenu<'frame, T> {
         })
     }
 
+    pub fn register_focus(&self) {
+        let state = self.state.clone();
+        self.ui.register_focus(self.state.borrow().selection, Rc::new(move |ui: &UI| {
+            // the widget may be busy handling the keypress that moved focus: then it's already focused
+            if let Ok(mut s) = state.try_borrow_mut() { ui.select(&mut *s) }
+        }))
+    }
+
+    pub(crate) fn widget(&self) -> Widget<T> {
+        Widget { state: self.state.clone() }
+    }
+
     pub(crate) fn with_context(mut self, on_ctx: impl FnOnce(&mut UIContext)) -> Self {
         self.ui = self.ui.with_context(on_ctx);
         self
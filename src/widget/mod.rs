@@ -10,11 +10,11 @@ use chiropterm::*;
 
 pub use self::common::WidgetCommon;
 pub use self::dimensions::{InternalWidgetDimensions, WidgetDimensions};
-pub use self::layout_hacks::LayoutHacks;
+pub use self::layout_hacks::{Align, LayoutHacks, Sides};
 pub use self::menu::WidgetMenu;
 pub use self::polymorphic::AnyWidget;
 
-use super::UI;
+use super::{MenuItem, PopupMenu, UI};
 
 pub struct Widget<T: Widgetlike> {
     // TODO: Instead use a ref inside an arena allocator (not bump, we need drop)
@@ -47,21 +47,46 @@ impl<T: Widgetlike> Widget<T> {
         self.share()
     }
 
+    // Opened by right-clicking anywhere the widget handles the mouse
+    pub fn set_context_menu(&self, items: impl IntoIterator<Item=MenuItem>) -> PopupMenu {
+        let popup = PopupMenu::new().setup(|p| {
+            for i in items { p.add_item(i) }
+        });
+        self.state.borrow_mut().context_menu = Some(popup.share());
+        popup
+    }
+
+    pub fn clear_context_menu(&self) {
+        self.state.borrow_mut().context_menu = None;
+    }
+
+    pub(crate) fn context_menu(&self) -> Option<PopupMenu> {
+        let explicit = self.state.borrow().context_menu.as_ref().map(|p| p.share());
+        explicit.or_else(|| T::default_context_menu(self))
+    }
+
     pub fn draw<'frame>(&self, ui: UI, brush: Brush, menu: Menu<'frame>) {
-        let brush = self.internal_estimate_dimensions(&ui, brush.rect().width()).tailor(brush);
+        let root = if ui.begin_draw() { Some((brush.clone(), menu.share())) } else { None };
+        if root.is_some() {
+            ui.set_last_root(self.share().into(), brush.rect().translate(brush.cursor_offset()));
+        }
+        let hacks = self.state.borrow().unique.layout_hacks();
+        let brush = self.internal_estimate_dimensions(&ui, brush.rect().width() - hacks.extra_width()).tailor(brush, hacks);
         let offset = brush.cursor_offset();
         let widget_menu = WidgetMenu { 
-            ui, state: self.state.clone(), menu, brush_offset: offset,
+            ui: ui.share(), state: self.state.clone(), menu, brush_offset: offset,
         };
         if brush.clip().is_empty() {
             self.state.borrow().skip_draw(brush, widget_menu)
         } else {
             self.state.borrow().draw(brush, widget_menu);
         }
+        ui.end_draw(root);
     }
 
     pub fn estimate_dimensions(&self, ui: &UI, width: isize) -> WidgetDimensions {
-        let mut dims = self.internal_estimate_dimensions(ui, width).to_external();
+        let hacks = self.state.borrow().unique.layout_hacks();
+        let mut dims = self.internal_estimate_dimensions(ui, width - hacks.extra_width()).to_external();
         dims = self.state.borrow().apply_layout_hacks(dims);
         dims
     }
@@ -71,7 +96,7 @@ impl<T: Widgetlike> Widget<T> {
         self.state.borrow().estimate_dimensions(ui, width)
     }
 
-    pub(in super) fn clear_layout_cache_if_needed(&self, ui: &UI) {
+    pub(crate) fn clear_layout_cache_if_needed(&self, ui: &UI) {
         self.state.borrow_mut().clear_layout_cache_if_needed(ui)
     }
 }
@@ -87,4 +112,7 @@ pub trait Widgetlike: 'static+Sized {
     fn estimate_dimensions(&self, ui: &UI, width: isize) ->InternalWidgetDimensions;
     fn clear_layout_cache(&self, ui: &UI);
     fn layout_hacks(&self) -> LayoutHacks;
+
+    // Used when the widget has no context menu of its own. It's rebuilt every time it opens
+    fn default_context_menu(_widget: &Widget<Self>) -> Option<PopupMenu> { None }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use chiropterm::{Brush, Menu};
 
 use crate::UI;
@@ -26,10 +28,20 @@ impl AnyWidget {
         self.implementation.poly_draw(ui, brush, menu);
     }
     
+    // Like Widget::draw: for drawing a tree from outside of any widget
+    pub fn draw_root<'frame>(&self, ui: UI, brush: Brush, menu: Menu<'frame>) {
+        self.implementation.poly_draw(ui, brush, menu);
+    }
+
     pub fn share(&self) -> AnyWidget {
         return self.implementation.poly_share()
     }
 
+    // True if both refer to the same widget, not just equal-looking ones
+    pub fn ptr_eq(&self, other: &AnyWidget) -> bool {
+        self.implementation.poly_ptr() == other.implementation.poly_ptr()
+    }
+
     pub(crate) fn clear_layout_cache_if_needed(&self, ui: &UI) {
         self.implementation.poly_clear_layout_cache_if_needed(ui)
     }
@@ -40,6 +52,7 @@ trait AWidget: 'static {
     fn poly_draw<'frame>(&self, ui: UI, brush: Brush, menu: Menu<'frame>);
     fn poly_clear_layout_cache_if_needed(&self, ui: &UI);
     fn poly_share(&self) -> AnyWidget;
+    fn poly_ptr(&self) -> *const ();
 }
 
 impl<T: Widgetlike> AWidget for Widget<T> {
@@ -58,6 +71,10 @@ impl<T: Widgetlike> AWidget for Widget<T> {
     fn poly_share(&self) -> AnyWidget {
         AnyWidget::wrap(self.share())
     }
+
+    fn poly_ptr(&self) -> *const () {
+        Rc::as_ptr(&self.state) as *const ()
+    }
 }
 
 impl<T: Widgetlike> From<Widget<T>> for AnyWidget {
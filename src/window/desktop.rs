@@ -0,0 +1,351 @@
+use chiropterm::*;
+use euclid::{point2, rect, size2};
+use smallvec::SmallVec;
+
+use crate::{InternalWidgetDimensions, UI, Widget, WidgetCommon, WidgetMenu, Widgetlike, input::text_width, layout::BulletinBoardState, widget::LayoutHacks};
+
+use super::Window;
+
+// Smallvec size -- set this to "more windows than most users will ever open on one desktop"
+const SM: usize = 32;
+
+pub type Desktop = Widget<DesktopState>;
+
+#[derive(Clone, Copy)]
+enum DragKind {
+    Move,
+    // which edges are being dragged: -1 the left or top one, 1 the right or bottom one, 0 neither
+    Resize { x: isize, y: isize },
+}
+
+// Windows that can be moved, resized, raised, minimized and closed: a BulletinBoard under the user's control.
+// A window's size is its preferred size, which resizing it overrides through its layout hacks
+pub struct DesktopState {
+    board: BulletinBoardState,  // the windows on screen, back to front
+    shown: SmallVec<[(u64, Window); SM]>,  // the same windows in the same order, with their ids
+    minimized: SmallVec<[(u64, Window, CellPoint); SM]>,  // and where they go back to
+    next_id: u64,
+    drag: Option<(u64, DragKind, CellRect)>,  // and the window's rect when the drag started
+
+    // runs before the window goes, so it can still look at it. Return false to keep it open
+    pub on_close: Option<Box<dyn FnMut(UI, &mut WidgetCommon<DesktopState>, Window) -> bool>>,
+    pub layout_hacks: LayoutHacks,
+}
+
+impl DesktopState {
+    pub fn add(&mut self, at: CellPoint, window: Window) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.shown.push((id, window.share()));
+        self.board.add(at, window);
+        id
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Window> {
+        if let Some(ix) = self.index_of(id) {
+            self.board.remove(ix);
+            return Some(self.shown.remove(ix).1)
+        }
+        let ix = self.minimized.iter().position(|(i, _, _)| *i == id)?;
+        Some(self.minimized.remove(ix).1)
+    }
+
+    pub fn window(&self, id: u64) -> Option<Window> {
+        self.shown.iter().map(|(i, w)| (i, w))
+            .chain(self.minimized.iter().map(|(i, w, _)| (i, w)))
+            .find(|(i, _)| **i == id)
+            .map(|(_, w)| w.share())
+    }
+
+    // The window on top, which is the only one drawn as active
+    pub fn active(&self) -> Option<Window> {
+        self.shown.last().map(|(_, w)| w.share())
+    }
+
+    pub fn raise(&mut self, id: u64) {
+        if let Some(ix) = self.index_of(id) {
+            let top = self.shown.len() - 1;
+            if ix == top { return }
+            let w = self.shown.remove(ix);
+            self.shown.push(w);
+            self.board.move_widget(ix, top);
+        }
+    }
+
+    pub fn minimize(&mut self, id: u64) {
+        if let Some(ix) = self.index_of(id) {
+            let at = self.board.iter().nth(ix).map(|(at, _)| at).unwrap_or(point2(0, 0));
+            self.board.remove(ix);
+            let (_, window) = self.shown.remove(ix);
+            self.minimized.push((id, window, at));
+        }
+    }
+
+    pub fn restore(&mut self, id: u64) {
+        if let Some(ix) = self.minimized.iter().position(|(i, _, _)| *i == id) {
+            let (_, window, at) = self.minimized.remove(ix);
+            self.shown.push((id, window.share()));
+            self.board.add(at, window);
+        } else {
+            self.raise(id);
+        }
+    }
+
+    pub fn set_bounds(&mut self, id: u64, bounds: CellRect) {
+        if let Some(ix) = self.index_of(id) {
+            self.board.set_position(ix, bounds.origin);
+            set_size(&self.shown[ix].1, bounds.size);
+        } else if let Some(m) = self.minimized.iter_mut().find(|(i, _, _)| *i == id) {
+            m.2 = bounds.origin;
+            set_size(&m.1, bounds.size);
+        }
+    }
+
+    pub fn set_on_close(&mut self, cmd: impl 'static+FnMut(UI, &mut WidgetCommon<DesktopState>, Window) -> bool) {
+        self.on_close = Some(Box::new(cmd))
+    }
+
+    // On the board, that is: minimized windows aren't
+    fn index_of(&self, id: u64) -> Option<usize> {
+        self.shown.iter().position(|(i, _)| *i == id)
+    }
+
+    fn start_drag(&mut self, id: u64, kind: DragKind, at: CellRect) {
+        self.raise(id);
+        self.drag = Some((id, kind, at));
+    }
+
+    fn drag_to(&mut self, ui: &UI, id: u64, delta: CellVector) {
+        let (drag_id, kind, start) = match self.drag { Some(d) => d, None => return };
+        if drag_id != id { return }
+        let ix = if let Some(ix) = self.index_of(id) { ix } else { return };
+
+        match kind {
+            DragKind::Move => { self.board.set_position(ix, start.origin + delta); }
+            DragKind::Resize { x, y } => {
+                let window = &self.shown[ix].1;
+                let mut size = start.size;
+                size.width += delta.x * x;
+                size.height += delta.y * y;
+                // no smaller than the window can go, ignoring any size it was given before
+                let min = window.borrow().estimate_dimensions(ui, size.width.max(0)).min;
+                let size = size.max(min);
+
+                // dragging the left or top edge moves the window too, so the opposite edge stays put
+                let mut origin = start.origin;
+                if x < 0 { origin.x = start.max_x() - size.width }
+                if y < 0 { origin.y = start.max_y() - size.height }
+                self.board.set_position(ix, origin);
+                set_size(window, size);
+                ui.recompute_layout();
+            }
+        }
+    }
+
+    fn close(ui: UI, this: &mut WidgetCommon<Self>, id: u64) -> Signal {
+        let window = if let Some(w) = this.unique.window(id) { w } else { return Signal::Continue };
+
+        let on_close = this.unique.on_close.take();
+        if let Some(mut c) = on_close {
+            let close = c(ui, this, window);
+            // unless the callback installed a new one
+            if this.unique.on_close.is_none() { this.unique.on_close = Some(c) }
+            if !close { return Signal::Refresh }
+        }
+
+        this.unique.remove(id);
+        Signal::Refresh
+    }
+}
+
+impl Widgetlike for DesktopState {
+    fn create() -> Self {
+        DesktopState {
+            board: BulletinBoardState::create(),
+            shown: SmallVec::new(),
+            minimized: SmallVec::new(),
+            next_id: 0,
+            drag: None,
+
+            on_close: None,
+            layout_hacks: LayoutHacks::new(),
+        }
+    }
+
+    fn draw<'frame>(&self, _: bool, brush: Brush, menu: WidgetMenu<'frame, Self>) {
+        let theme = menu.ui.theme();
+        let bounds = brush.rect().size;
+
+        // under everything: a mouse-up that misses every window still ends a drag
+        let backdrop_interactor = menu.on_mouse(move |_, this, click: MouseEvent| {
+            match click {
+                MouseEvent::Click(_, _, _) => {}
+                MouseEvent::Up(_, _, _) => { this.unique.drag = None; }
+                MouseEvent::Drag {..} => {}
+                MouseEvent::Scroll(_, _, _) => {}
+                MouseEvent::Wiggle {..} => {}
+            };
+            Signal::Continue
+        });
+        brush.interactor(backdrop_interactor, theme.base.wallpaper).fill(FSem::new());
+
+        let top = self.shown.len().checked_sub(1);
+        for (ix, (_, r)) in self.board.placed(&menu.ui, bounds).enumerate() {
+            let (id, window) = &self.shown[ix];
+            let active = Some(ix) == top;
+            let win_brush = brush.region(r);
+            window.draw(
+                menu.ui.share().with_context(|c| c.active = active), 
+                win_brush.clone(), menu.menu.share(),
+            );
+            draw_chrome(*id, window, r, active, win_brush, &menu);
+        }
+
+        // minimized windows line up along the bottom
+        let mut x = 0;
+        for (id, window, _) in self.minimized.iter() {
+            let id = *id;
+            let title = window.borrow().unique.get_title().unwrap_or("Window").to_owned();
+            let w = text_width(&title) as isize + 4;
+
+            let restore_interactor = menu.on_mouse(move |_, this, click: MouseEvent| {
+                match click {
+                    MouseEvent::Click(MouseButton::Left, _, _) => {
+                        this.unique.restore(id);
+                        return Signal::Refresh
+                    }
+                    MouseEvent::Click(_, _, _) => {}
+                    MouseEvent::Up(_, _, _) => { this.unique.drag = None; }
+                    MouseEvent::Drag {..} => {}
+                    MouseEvent::Scroll(_, _, _) => {}
+                    MouseEvent::Wiggle {..} => {}
+                };
+                Signal::Continue
+            });
+
+            let icon = brush.region(rect(x, bounds.height - 2, w, 2));
+            icon.fill(FSem::new().sem(SemanticContent::Blank).color(theme.window.color));
+            icon.bevel_w95(theme.button.bevel);
+            icon.region(rect(2, 0, w - 4, 2)).interactor(restore_interactor, theme.button.preclick).putfs(&title);
+            x += w;
+        }
+    }
+
+    fn estimate_dimensions(&self, ui: &UI, width: isize) -> InternalWidgetDimensions {
+        InternalWidgetDimensions {
+            // windows get edged onto the desktop, however small it is
+            min: size2(0, 0),
+            ..self.board.estimate_dimensions(ui, width)
+        }
+    }
+
+    fn clear_layout_cache(&self, ui: &UI) {
+        self.board.clear_layout_cache(ui);
+        for (_, window, _) in self.minimized.iter() {
+            window.clear_layout_cache_if_needed(&ui)
+        }
+    }
+
+    fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
+}
+
+fn set_size(window: &Window, size: CellSize) {
+    let hacks = &mut window.borrow_mut().unique.layout_hacks;
+    hacks.preferred_width = Some(size.width.max(0) as usize);
+    hacks.preferred_height = Some(size.height.max(0) as usize);
+}
+
+// Interactors over the window's title bar, buttons and edges. They're drawn over the window, so they take priority.
+// Every edge resizes, except that the top one is the title bar: there, only the corners do
+fn draw_chrome<'frame>(id: u64, window: &Window, at: CellRect, active: bool, brush: Brush, menu: &WidgetMenu<'frame, DesktopState>) {
+    let theme = menu.ui.theme();
+    let size = brush.rect().size;
+    let title_height = if window.borrow().unique.get_title().is_some() { 2 } else { 1 };
+
+    if !active {
+        // the first click on a background window just brings it forward
+        let raise_interactor = menu.on_mouse(move |_, this, click: MouseEvent| {
+            match click {
+                MouseEvent::Click(_, _, _) => {
+                    this.unique.raise(id);
+                    return Signal::Refresh
+                }
+                MouseEvent::Up(_, _, _) => { this.unique.drag = None; }
+                MouseEvent::Drag {..} => {}
+                MouseEvent::Scroll(_, _, _) => {}
+                MouseEvent::Wiggle {..} => {}
+            };
+            Signal::Continue
+        });
+        brush.interactor(raise_interactor, theme.window.color).fill(FSem::new());
+    }
+
+    let drag = |kind: DragKind| menu.on_mouse(move |ui, this, click: MouseEvent| {
+        match click {
+            MouseEvent::Click(MouseButton::Left, _, _) => {
+                this.unique.start_drag(id, kind, at);
+                return Signal::Refresh
+            }
+            MouseEvent::Click(_, _, _) => {}
+            MouseEvent::Up(_, _, _) => { this.unique.drag = None; }
+            MouseEvent::Drag { mouse_button: MouseButton::Left, start_point, now_point, .. } => {
+                this.unique.drag_to(&ui, id, now_point - start_point);
+                return Signal::Refresh
+            }
+            MouseEvent::Drag {..} => {}
+            MouseEvent::Scroll(_, _, _) => {}
+            MouseEvent::Wiggle {..} => {}
+        };
+        Signal::Continue
+    });
+
+    let title_interactor = drag(DragKind::Move);
+    brush.region(rect(1, 0, size.width - 6, title_height)).interactor(title_interactor, theme.window.color).fill(FSem::new());
+
+    let (w, h) = (size.width, size.height);
+    let edges = [
+        // x, y, width, height, and which way each edge moves
+        (0, 0, 1, 1, -1, -1),
+        (w - 1, 0, 1, 1, 1, -1),
+        (0, 1, 1, h - 2, -1, 0),
+        (w - 1, 1, 1, h - 2, 1, 0),
+        (1, h - 1, w - 2, 1, 0, 1),
+        (0, h - 1, 1, 1, -1, 1),
+        (w - 1, h - 1, 1, 1, 1, 1),
+    ];
+    for (x, y, ew, eh, dx, dy) in edges {
+        let interactor = drag(DragKind::Resize { x: dx, y: dy });
+        brush.region(rect(x, y, ew, eh)).interactor(interactor, theme.button.preclick).fill(FSem::new());
+    }
+
+    let minimize_interactor = menu.on_mouse(move |_, this, click: MouseEvent| {
+        match click {
+            MouseEvent::Click(MouseButton::Left, _, _) => {
+                this.unique.minimize(id);
+                return Signal::Refresh
+            }
+            MouseEvent::Click(_, _, _) => {}
+            MouseEvent::Up(_, _, _) => { this.unique.drag = None; }
+            MouseEvent::Drag {..} => {}
+            MouseEvent::Scroll(_, _, _) => {}
+            MouseEvent::Wiggle {..} => {}
+        };
+        Signal::Continue
+    });
+    let close_interactor = menu.on_mouse(move |ui, this, click: MouseEvent| {
+        match click {
+            MouseEvent::Click(MouseButton::Left, _, _) => {
+                return DesktopState::close(ui, this, id)
+            }
+            MouseEvent::Click(_, _, _) => {}
+            MouseEvent::Up(_, _, _) => { this.unique.drag = None; }
+            MouseEvent::Drag {..} => {}
+            MouseEvent::Scroll(_, _, _) => {}
+            MouseEvent::Wiggle {..} => {}
+        };
+        Signal::Continue
+    });
+    let buttons = brush.region(rect(size.width - 5, 0, 4, title_height.max(2)));
+    buttons.region(rect(0, 0, 1, 2)).interactor(minimize_interactor, theme.button.preclick).putch('_' as u16);
+    buttons.region(rect(2, 0, 1, 2)).interactor(close_interactor, theme.button.preclick).putch('x' as u16);
+}
@@ -1,7 +1,11 @@
+mod desktop;
+
 use chiropterm::*;
 use euclid::{rect, size2};
 
-use super::{InternalWidgetDimensions, Label, UI, Widget, WidgetMenu, Widgetlike, look_and_feel::WindowBorders, widget::{AnyWidget, LayoutHacks}};
+use super::{InternalWidgetDimensions, Label, MenuBar, UI, Widget, WidgetMenu, Widgetlike, look_and_feel::WindowBorders, widget::{AnyWidget, LayoutHacks}};
+
+pub use self::desktop::{Desktop, DesktopState};
 
 pub type Window = Widget<WindowState>;
 
@@ -11,8 +15,11 @@ pub struct WindowState {
     title: Option<Label>,
     title_text: Option<String>,  // all labels are potentially shared, so we have to clone it to provide a getter
     widget: Option<AnyWidget>,
+    menu_bar: Option<MenuBar>,
 
     pub window_border_override: Option<WindowBorders>,
+    // if set, Tab/Shift-Tab never leave this window once something inside it is focused
+    pub focus_scope: bool,
     pub layout_hacks: LayoutHacks,
 }
 
@@ -22,8 +29,10 @@ impl Widgetlike for WindowState {
             title: None,
             title_text: None,
             widget: None,
+            menu_bar: None,
 
             window_border_override: None,
+            focus_scope: false,
             layout_hacks: LayoutHacks::new(),
         }
     }
@@ -87,10 +96,22 @@ impl Widgetlike for WindowState {
             }
         };
 
+        let inner = match &self.menu_bar {
+            Some(bar) => {
+                let size = inner.rect().size;
+                bar.draw(menu.ui.share(), inner.region(rect(0, 0, size.width, 2)), menu.menu.share());
+                inner.region(rect(0, 2, size.width, size.height - 2))
+            }
+            None => inner
+        };
+
+        let ui = menu.ui.share();
+        if self.focus_scope { ui.begin_focus_scope(); }
         match &self.widget {
             Some(x) => x.draw(inner, menu),
             None => {}
         }
+        if self.focus_scope { ui.end_focus_scope(); }
     }
 
     fn estimate_dimensions(&self, ui: &UI, width: isize) -> InternalWidgetDimensions {
@@ -107,6 +128,7 @@ impl Widgetlike for WindowState {
                 ((4, 4), (2, 2))
             }
         };
+        let pad_y = if self.menu_bar.is_some() { pad_y + 2 } else { pad_y };
         let d1 = if let Some(w) = self.widget.as_ref() {
             w.estimate_dimensions(ui, width - pad_x)
         } else {
@@ -130,6 +152,9 @@ impl Widgetlike for WindowState {
         if let Some(w) = self.widget.as_ref() {
             w.clear_layout_cache_if_needed(ui)
         }
+        if let Some(b) = self.menu_bar.as_ref() {
+            b.clear_layout_cache_if_needed(ui)
+        }
     }
 
     fn layout_hacks(&self) -> LayoutHacks { self.layout_hacks }
@@ -139,6 +164,11 @@ impl WindowState {
     pub fn set<X: Into<AnyWidget>>(&mut self, w: X) {
         self.widget = Some(w.into())
     }
+
+    // Drawn just under the title bar
+    pub fn set_menu_bar(&mut self, bar: MenuBar) {
+        self.menu_bar = Some(bar)
+    }
 }
 
 // TODO: Do a title bar theme that uses code similar to this to just do a Powerline bar
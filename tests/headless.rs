@@ -0,0 +1,83 @@
+use std::{cell::Cell, rc::Rc};
+
+use chiropterm::*;
+use chiroptui::{*, headless::Headless};
+use euclid::{point2, size2};
+
+fn golden(name: &str) -> String {
+    format!("{}/tests/golden/{}.txt", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn label(text: &str) -> Label {
+    Label::new().setup(|l| l.set_text(text))
+}
+
+#[test]
+fn column_stacks_children() {
+    let h = Headless::new(Theme::W95_FRUITY, size2(20, 8));
+    let col = Column::new().setup(|c| {
+        c.add(label("top"));
+        c.add(label("bottom"));
+    });
+
+    let snap = h.render(&col.into());
+    assert!(snap.glyphs().lines().nth(0).unwrap().starts_with("top"));
+    assert!(snap.glyphs().lines().nth(2).unwrap().starts_with("bottom"));
+    snap.assert_golden(golden("column_stacks_children"));
+}
+
+#[test]
+fn row_shares_width() {
+    let h = Headless::new(Theme::W95_FRUITY, size2(20, 2));
+    let row = Row::new().setup(|r| {
+        r.add(label("left"));
+        r.add(Spacer::new());
+        r.add(label("right"));
+    });
+
+    let snap = h.render(&row.into());
+    let line = snap.glyphs().lines().nth(0).unwrap().to_owned();
+    assert!(line.starts_with("left"));
+    assert!(line.ends_with("right"));
+    snap.assert_golden(golden("row_shares_width"));
+}
+
+#[test]
+fn scrollable_shows_scrollbar_when_content_overflows() {
+    let h = Headless::new(Theme::W95_FRUITY, size2(20, 6));
+    let col = Column::new().setup(|c| {
+        for i in 0..10 { c.add(label(&format!("line {}", i))); }
+    });
+    let scrollable = Scrollable::new().setup(|s| s.set(col));
+
+    h.render(&scrollable.into()).assert_golden(golden("scrollable_overflow"));
+}
+
+#[test]
+fn window_draws_title_and_content() {
+    let h = Headless::new(Theme::W95_FRUITY, size2(24, 10));
+    let window = Window::new().setup(|w| {
+        w.set_title("TITLE");
+        w.set(label("content"));
+    });
+
+    let snap = h.render(&window.into());
+    assert!(snap.glyphs().contains("TITLE"));
+    assert!(snap.glyphs().contains("content"));
+    snap.assert_golden(golden("window_title_and_content"));
+}
+
+#[test]
+fn clicking_a_button_runs_its_command() {
+    let h = Headless::new(Theme::W95_FRUITY, size2(20, 2));
+    let clicked = Rc::new(Cell::new(false));
+    let c2 = clicked.clone();
+    let button = Button::new().setup(|b| {
+        b.text = "Press".to_owned();
+        b.set_command(move |_, _, _| { c2.set(true); Signal::Refresh });
+    });
+
+    let signal = h.mouse(&button.into(), MouseEvent::Click(MouseButton::Left, point2(1, 0), false));
+    assert!(matches!(signal, Some(Signal::Refresh)));
+    assert!(clicked.get());
+}